@@ -0,0 +1,30 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+
+use pinball3d::regression::{simulation_with_balls, BENCHMARK_BALL_COUNTS};
+
+//Ticks measured on every fresh table. One second of play, short enough that balls have not yet
+//drained or been collected.
+const TICKS_PER_TABLE: u64 = 60;
+
+fn simulation_throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("simulation_tick");
+    group.throughput(Throughput::Elements(TICKS_PER_TABLE));
+    for balls in BENCHMARK_BALL_COUNTS {
+        group.bench_with_input(BenchmarkId::from_parameter(balls), &balls, |b, &balls| {
+            b.iter_batched(
+                || simulation_with_balls(balls),
+                |mut simulation| {
+                    for _ in 0..TICKS_PER_TABLE {
+                        simulation.step();
+                    }
+                    simulation
+                },
+                BatchSize::PerIteration,
+            );
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, simulation_throughput);
+criterion_main!(benches);
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::is_ball_on_launcher;
+use super::Ball;
+use super::Floor;
+use super::PLUNGER_FULL_CHARGE_SECONDS;
+use super::{ActionSystem, InputAction, VirtualActions};
+use super::{Flipper, FLIPPER_LENGTH};
+
+pub struct AutopilotPlugin;
+
+impl Plugin for AutopilotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Autopilot>()
+            .init_resource::<AutopilotAssist>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                autopilot_flippers.before(ActionSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                autopilot_launcher.before(ActionSystem),
+            );
+    }
+}
+
+// When true the autopilot works flippers and launcher. Used for attract mode, soak tests and simulations.
+#[derive(Resource, Default)]
+pub struct Autopilot(pub bool);
+
+// Parts of the autopilot that help a player who plays the rest, as in one-switch mode.
+#[derive(Resource, Default)]
+pub struct AutopilotAssist {
+    pub flippers: bool,
+    pub launcher: bool,
+}
+
+//How far ahead ball paths are predicted, and in what steps.
+const PREDICTION_HORIZON: f32 = 0.5;
+const PREDICTION_STEP: f32 = 0.005;
+//Flip when the ball is predicted to reach the flipper within this time. About the time a flipper needs to swing up.
+const FLIP_LEAD_TIME: f32 = 0.06;
+//Keep the flipper up for at least this long once fired.
+const FLIP_HOLD_TIME: f32 = 0.25;
+//Ball radius plus some margin.
+const FLIPPER_REACH: f32 = 0.02;
+//Seconds the launcher is held, and the pause after a launch. Three quarters of a full pull
+//launches about as hard as the old push launcher did.
+const LAUNCH_HOLD_TIME: f32 = 0.75 * PLUNGER_FULL_CHARGE_SECONDS;
+const LAUNCH_COOLDOWN_TIME: f32 = 1.0;
+
+fn autopilot_flippers(
+    autopilot: Res<Autopilot>,
+    assist: Res<AutopilotAssist>,
+    mut virtual_actions: ResMut<VirtualActions>,
+    query_balls: Query<(&Transform, &Velocity), With<Ball>>,
+    query_flippers: Query<(&Flipper, &Transform)>,
+    query_floors: Query<&GlobalTransform, With<Floor>>,
+    rapier_config: Res<RapierConfiguration>,
+    time: Res<Time>,
+    mut hold_times: Local<HashMap<InputAction, f32>>,
+) {
+    if !autopilot.0 && !assist.flippers {
+        return;
+    }
+    let floor_transform = match query_floors.iter().next() {
+        Some(floor_transform) => floor_transform,
+        None => return,
+    };
+
+    //Flippers are children of the floor, so predict in floor coordinates.
+    let world_to_floor = floor_transform.compute_matrix().inverse();
+    let gravity = world_to_floor
+        .transform_vector3(rapier_config.gravity)
+        .truncate();
+
+    for hold_time in hold_times.values_mut() {
+        *hold_time = (*hold_time - time.delta_seconds()).max(0.0);
+    }
+    for (flipper, flipper_transform) in query_flippers.iter() {
+        let arriving = query_balls.iter().any(|(ball_transform, ball_velocity)| {
+            let position = world_to_floor
+                .transform_point3(ball_transform.translation)
+                .truncate();
+            let velocity = world_to_floor
+                .transform_vector3(ball_velocity.linvel)
+                .truncate();
+            is_ball_arriving(position, velocity, gravity, flipper_transform)
+        });
+        let hold_time = hold_times.entry(flipper.action).or_insert(0.0);
+        if arriving {
+            *hold_time = FLIP_HOLD_TIME;
+        }
+    }
+
+    for (action, hold_time) in hold_times.iter() {
+        virtual_actions.set(*action, *hold_time > 0.0);
+    }
+}
+
+//Follow the ball path under gravity and check if it comes within reach of the flipper soon.
+fn is_ball_arriving(
+    position: Vec2,
+    velocity: Vec2,
+    gravity: Vec2,
+    flipper_transform: &Transform,
+) -> bool {
+    let pivot = flipper_transform.translation.truncate();
+    let tip = pivot + (flipper_transform.rotation * Vec3::X).truncate() * FLIPPER_LENGTH;
+
+    //Balls below the flipper can not be hit.
+    if position.y < pivot.y.min(tip.y) - FLIPPER_REACH {
+        return false;
+    }
+
+    let mut t = 0.0;
+    while t <= PREDICTION_HORIZON {
+        let predicted = position + velocity * t + 0.5 * gravity * t * t;
+        if distance_to_segment(predicted, pivot, tip) < FLIPPER_REACH {
+            return t <= FLIP_LEAD_TIME;
+        }
+        t += PREDICTION_STEP;
+    }
+    false
+}
+
+fn distance_to_segment(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let segment = end - start;
+    let along = ((point - start).dot(segment) / segment.length_squared()).clamp(0.0, 1.0);
+    point.distance(start + segment * along)
+}
+
+fn autopilot_launcher(
+    autopilot: Res<Autopilot>,
+    assist: Res<AutopilotAssist>,
+    mut virtual_actions: ResMut<VirtualActions>,
+    query_balls: Query<(&Transform, &Velocity), With<Ball>>,
+    time: Res<Time>,
+    mut hold_time: Local<f32>,
+    mut cooldown_time: Local<f32>,
+) {
+    if !autopilot.0 && !assist.launcher {
+        return;
+    }
+
+    if *hold_time > 0.0 {
+        *hold_time -= time.delta_seconds();
+        if *hold_time <= 0.0 {
+            virtual_actions.set(InputAction::Launch, false);
+            *cooldown_time = LAUNCH_COOLDOWN_TIME;
+        }
+        return;
+    }
+    if *cooldown_time > 0.0 {
+        *cooldown_time -= time.delta_seconds();
+        return;
+    }
+
+    if query_balls
+        .iter()
+        .any(|(ball_transform, ball_velocity)| is_ball_on_launcher(ball_transform, ball_velocity))
+    {
+        virtual_actions.set(InputAction::Launch, true);
+        *hold_time = LAUNCH_HOLD_TIME;
+    }
+}
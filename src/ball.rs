@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
 use super::BottomWall;
+use super::{ActionState, InputAction};
 
 use super::common;
 
@@ -10,7 +11,9 @@ pub struct BallPlugin;
 impl Plugin for BallPlugin {
     fn build(&self, app: &mut App) {
         app.add_startup_system_to_stage(StartupStage::PostStartup, spawn_balls)
-            .add_system(push_ball_to_floor)
+            .add_system(push_ball_to_floor.label(BallForceSystem))
+            .add_system(nudge_table)
+            .add_system(apply_nudges)
             .add_system(handle_ball_intersections_with_bottom_wall);
     }
 }
@@ -18,6 +21,11 @@ impl Plugin for BallPlugin {
 #[derive(Component)]
 pub struct Ball;
 
+//Label for the system that sets the force on every ball each frame. Systems that add to
+//a ball's ExternalForce run after this.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+pub struct BallForceSystem;
+
 #[derive(Default, Component)]
 pub struct MaterialColor(pub Color);
 
@@ -28,7 +36,18 @@ struct InitBallBundle {
 
 pub const INIT_BALL_POSITION: Vec3 = Vec3::new(0.32, -0.83, 0.02);
 
-fn spawn_balls(
+//Elements that fit around a ball use this too.
+pub const BALL_RADIUS: f32 = 0.015;
+
+//Impulse given to every ball by a nudge of length 1.
+pub const NUDGE_IMPULSE: f32 = 0.000004;
+
+//Balls in the star collector get GROUP_5 added to their filters, so the one way gate keeps them in.
+pub fn is_ball_in_star_collector(collision_groups: &CollisionGroups) -> bool {
+    (collision_groups.filters & Group::GROUP_5) == Group::GROUP_5
+}
+
+pub fn spawn_balls(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
@@ -61,7 +80,7 @@ pub fn spawn_single_ball(
     commands
         .spawn(PbrBundle {
             mesh: meshes.add(Mesh::from(shape::UVSphere {
-                radius: 0.015,
+                radius: BALL_RADIUS,
                 ..default()
             })),
             material: materials.add(material_color.0.into()),
@@ -74,7 +93,7 @@ pub fn spawn_single_ball(
             coefficient: 0.1,
             combine_rule: CoefficientCombineRule::Min,
         })
-        .insert(Collider::ball(0.015))
+        .insert(Collider::ball(BALL_RADIUS))
         .insert(TransformBundle::from(Transform::from_xyz(
             position.x, position.y, position.z,
         )))
@@ -131,24 +150,56 @@ fn push_ball_to_floor(
             } else {
                 ball_force.force = Vec3::new(0.0, 0.0, 0.0);
             }
+        } else {
+            //Start from no force, so forces added after this do not build up over frames.
+            ball_force.force = Vec3::new(0.0, 0.0, 0.0);
+        }
+    }
+}
+
+fn nudge_table(action_state: Res<ActionState>, mut nudges: EventWriter<common::Nudge>) {
+    if action_state.just_pressed(InputAction::Nudge) {
+        let direction = if action_state.nudge_direction == Vec2::ZERO {
+            Vec2::Y
+        } else {
+            action_state.nudge_direction
+        };
+        nudges.send(common::Nudge { direction });
+    }
+}
+
+//Nudging moves the table, seen from the balls it is a push the opposite way.
+fn apply_nudges(
+    mut nudges: EventReader<common::Nudge>,
+    mut query_balls: Query<&mut ExternalImpulse, With<Ball>>,
+) {
+    for nudge in nudges.iter() {
+        let impulse = -nudge.direction.extend(0.0) * NUDGE_IMPULSE;
+        for mut external_impulse in query_balls.iter_mut() {
+            external_impulse.impulse += impulse;
         }
     }
 }
 
 fn handle_ball_intersections_with_bottom_wall(
     rapier_context: Res<RapierContext>,
-    query_ball: Query<(Entity, &MaterialColor), With<Ball>>,
+    query_ball: Query<(Entity, &MaterialColor, &Transform), With<Ball>>,
     query_bottom_wall: Query<Entity, With<BottomWall>>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     end_game: Res<common::EndGame>,
+    mut ball_drained: EventWriter<common::BallDrained>,
 ) {
     for entity_bottom_wall in query_bottom_wall.iter() {
-        for (entity_ball, material_color) in query_ball.iter() {
+        for (entity_ball, material_color, ball_transform) in query_ball.iter() {
             /* Find the intersection pair, if it exists, between two colliders. */
             if rapier_context.intersection_pair(entity_bottom_wall, entity_ball) == Some(true) {
                 commands.entity(entity_ball).despawn();
+                ball_drained.send(common::BallDrained {
+                    ball: entity_ball,
+                    position: ball_transform.translation,
+                });
                 if end_game.0 == false {
                     spawn_single_ball(
                         &mut commands,
@@ -19,6 +19,9 @@ impl Plugin for BumperPlugin {
     }
 }
 
+//Impulse given to a ball, along its own velocity, when it leaves a bumper.
+pub const BUMPER_PUSH_IMPULSE: f32 = 0.000003;
+
 #[derive(Default, Component)]
 struct Bumper;
 
@@ -43,7 +46,7 @@ pub struct BumperBundle {
     pub despawn_in_endgame: bool,
 }
 
-fn spawn_bumpers(
+pub fn spawn_bumpers(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
@@ -172,7 +175,7 @@ fn change_bumper_to_dark_color(
 ) {
     //for (entity, position, rotation, timestamp_last_hit, dark_color, light_color) in query_bumpers.iter_mut() {
     for (timestamp_last_hit, dark_color, mut material) in query_bumpers.iter_mut() {
-        let diff = time.raw_elapsed_seconds_f64() - timestamp_last_hit.0;
+        let diff = time.elapsed_seconds_f64() - timestamp_last_hit.0;
         if timestamp_last_hit.0 > 0.0 && diff > 1.0 {
             //Color have been toggled for more than a second so respawn
             //let pos = position;
@@ -202,6 +205,7 @@ fn handle_bumper_events(
     //mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     //query_floors: Query<(Entity, &HalfHeight), With<Floor>>,
+    mut element_hits: EventWriter<common::ElementHit>,
 ) {
     for contact_event in contact_events.iter() {
         for (entity, mut timestamp_last_hit, light_color, mut material) in query_bumpers.iter_mut()
@@ -209,7 +213,7 @@ fn handle_bumper_events(
             if let CollisionEvent::Started(h1, h2, _event_flag) = contact_event {
                 if h1 == &entity || h2 == &entity {
                     //Change to light color
-                    *timestamp_last_hit = TimestampLastHit(time.raw_elapsed_seconds_f64());
+                    *timestamp_last_hit = TimestampLastHit(time.elapsed_seconds_f64());
                     //commands.entity(entity).despawn();
                     //spawn_single_bumper(&mut commands, position, rotation, Some(timestamp_last_hit), dark_color, light_color, &mut meshes, &mut materials, &query_floors);
                     let light_material_bumper = materials.add(light_color.0.into());
@@ -221,9 +225,15 @@ fn handle_bumper_events(
                     //Give ball a push in velocity direction
                     for (entity_ball, mut external_impulse, velocity) in query_balls.iter_mut() {
                         if h1 == &entity_ball || h2 == &entity_ball {
-                            let normalized_velocity = velocity.linvel.normalize();
-                            external_impulse.impulse =
-                                external_impulse.impulse.add(normalized_velocity * 0.000003);
+                            let normalized_velocity = velocity.linvel.normalize_or_zero();
+                            let impulse = normalized_velocity * BUMPER_PUSH_IMPULSE;
+                            external_impulse.impulse = external_impulse.impulse.add(impulse);
+                            element_hits.send(common::ElementHit {
+                                kind: common::ElementKind::Bumper,
+                                element: entity,
+                                ball: entity_ball,
+                                impulse,
+                            });
                         }
                     }
                 }
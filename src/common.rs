@@ -1,5 +1,7 @@
 use bevy::prelude::*;
-//use bevy_rapier3d::prelude::*;
+use bevy_rapier3d::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
 #[derive(Default, Component)]
 pub struct Position(pub Vec3);
@@ -13,3 +15,127 @@ pub struct DespawnInEndGame;
 // This resource tracks when game is in it's last phase. All collected balls are released. And spawning of new balls is stopped.
 #[derive(Resource)]
 pub struct EndGame(pub bool);
+
+// Kind of gameplay element that gave a ball a push.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ElementKind {
+    Bumper,
+    Pin,
+    Target,
+    PopBumper,
+    Slingshot,
+    Kickback,
+    Saucer,
+}
+
+impl ElementKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ElementKind::Bumper => "bumper",
+            ElementKind::Pin => "pin",
+            ElementKind::Target => "target",
+            ElementKind::PopBumper => "pop bumper",
+            ElementKind::Slingshot => "slingshot",
+            ElementKind::Kickback => "kickback",
+            ElementKind::Saucer => "saucer",
+        }
+    }
+}
+
+// Event sent every time a gameplay element adds an impulse to a ball.
+pub struct ElementHit {
+    pub kind: ElementKind,
+    pub element: Entity,
+    pub ball: Entity,
+    pub impulse: Vec3,
+}
+
+// Event sent when a ball knocks down a drop target.
+pub struct DropTargetDown {
+    pub bank: Entity,
+    pub target: Entity,
+    pub ball: Entity,
+}
+
+// Event sent when the last target of a drop target bank goes down.
+pub struct DropTargetBankComplete {
+    pub bank: Entity,
+}
+
+// Event sent each time a spinner makes a full turn.
+pub struct SpinnerSpin {
+    pub spinner: Entity,
+}
+
+// Event sent when a ball rolls over a rollover lane sensor.
+pub struct RolloverLanePassed {
+    pub lane: usize,
+    pub ball: Entity,
+}
+
+// Event sent when a ball goes up a ramp from its entry to its exit.
+pub struct RampShot {
+    pub ramp: Entity,
+    pub ball: Entity,
+}
+
+// Event sent when a saucer catches a ball. Rules can run while the ball is held.
+pub struct SaucerHold {
+    pub saucer: Entity,
+    pub ball: Entity,
+}
+
+// Event sent when a ball falls through the bottom wall. Position is in world coordinates.
+pub struct BallDrained {
+    pub ball: Entity,
+    pub position: Vec3,
+}
+
+// True while the game is paused. Physics, timers and gameplay input stop.
+#[derive(Resource, Default)]
+pub struct Paused(pub bool);
+
+// Points scored so far in this game.
+#[derive(Resource, Default)]
+pub struct Score(pub u64);
+
+// Every score is multiplied by this. Raised by completing the rollover lanes.
+#[derive(Resource)]
+pub struct BonusMultiplier(pub u32);
+
+impl Default for BonusMultiplier {
+    fn default() -> Self {
+        BonusMultiplier(1)
+    }
+}
+
+// Random numbers for everything in the game left to chance. Seeded from entropy, simulations
+// seed it themselves so a run can be repeated.
+#[derive(Resource)]
+pub struct GameRng(pub StdRng);
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng(StdRng::from_entropy())
+    }
+}
+
+// Event for nudging the table. Direction is in the floor plane, length 1 gives a normal nudge.
+pub struct Nudge {
+    pub direction: Vec2,
+}
+
+//Time the physics world advances this frame. Kinematic bodies move by this, so they move the
+//same at every frame rate and keep pace with the balls when the physics is slowed down.
+pub fn physics_dt(rapier_config: &RapierConfiguration, time: &Time) -> f32 {
+    if !rapier_config.physics_pipeline_active {
+        return 0.0;
+    }
+    match rapier_config.timestep_mode {
+        TimestepMode::Fixed { dt, .. } => dt,
+        TimestepMode::Variable {
+            max_dt, time_scale, ..
+        } => (time.delta_seconds() * time_scale).min(max_dt),
+        TimestepMode::Interpolated { time_scale, .. } => time.delta_seconds() * time_scale,
+    }
+}
@@ -0,0 +1,370 @@
+use bevy::prelude::*;
+
+use super::{ControlBindings, InputAction, InputCaptured, OneSwitch};
+
+pub struct ControlsMenuPlugin;
+
+impl Plugin for ControlsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ControlsMenu>()
+            .add_startup_system(spawn_controls_menu)
+            .add_system(toggle_controls_menu)
+            .add_system(handle_binding_buttons)
+            .add_system(handle_menu_buttons)
+            .add_system(capture_binding_key)
+            .add_system(capture_binding_mouse_button)
+            .add_system(capture_binding_gamepad_button)
+            .add_system(
+                show_controls_menu
+                    .after(toggle_controls_menu)
+                    .after(handle_menu_buttons),
+            )
+            .add_system(update_binding_texts);
+    }
+}
+
+//Opens and closes the controls menu. Not an action, so it can not be unbound.
+const CONTROLS_MENU_KEY: KeyCode = KeyCode::F1;
+//Stops waiting for a key without binding one.
+const CANCEL_BINDING_KEY: KeyCode = KeyCode::Escape;
+//Takes the mouse buttons away from the action that is waiting.
+const CLEAR_MOUSE_BINDING_KEY: KeyCode = KeyCode::Delete;
+
+const BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const WAITING_BUTTON_COLOR: Color = Color::rgb(0.35, 0.35, 0.1);
+
+#[derive(Resource, Default)]
+pub struct ControlsMenu {
+    pub open: bool,
+    // Action that gets the next key, mouse button or gamepad button pressed.
+    pub waiting_for: Option<InputAction>,
+    // Set once all mouse buttons are up after waiting started, so the click on the action
+    // does not bind itself.
+    mouse_armed: bool,
+}
+
+impl ControlsMenu {
+    //Closes the menu without binding anything.
+    pub fn close(&mut self) {
+        self.open = false;
+        self.waiting_for = None;
+    }
+}
+
+#[derive(Component)]
+struct ControlsMenuRoot;
+
+#[derive(Component)]
+struct BindingButton(InputAction);
+
+#[derive(Component)]
+struct BindingText(InputAction);
+
+#[derive(Component, Clone, Copy)]
+enum ControlsMenuButton {
+    WheelPlunger,
+    OneSwitchMode,
+    OneSwitchPlunger,
+    GameSpeed,
+    Close,
+}
+
+// Text of a menu button that shows a setting.
+#[derive(Component)]
+struct SettingText(ControlsMenuButton);
+
+fn spawn_controls_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("fonts/DejaVuSans.ttf");
+    let text_style = TextStyle {
+        font: font.clone(),
+        font_size: 18.0,
+        color: Color::WHITE,
+    };
+    let button_bundle = ButtonBundle {
+        style: Style {
+            size: Size::new(Val::Px(300.0), Val::Px(36.0)),
+            margin: UiRect::all(Val::Px(4.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: BUTTON_COLOR.into(),
+        ..default()
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+            ..default()
+        })
+        .insert(ControlsMenuRoot)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Controls",
+                TextStyle {
+                    font_size: 28.0,
+                    ..text_style.clone()
+                },
+            ));
+
+            for action in InputAction::ALL {
+                parent
+                    .spawn(button_bundle.clone())
+                    .insert(BindingButton(action))
+                    .with_children(|button| {
+                        button
+                            .spawn(TextBundle::from_section("", text_style.clone()))
+                            .insert(BindingText(action));
+                    });
+            }
+
+            //Settings, one-switch mode among them, so it can be reached without command line.
+            for setting in [
+                ControlsMenuButton::WheelPlunger,
+                ControlsMenuButton::OneSwitchMode,
+                ControlsMenuButton::OneSwitchPlunger,
+                ControlsMenuButton::GameSpeed,
+            ] {
+                parent
+                    .spawn(button_bundle.clone())
+                    .insert(setting)
+                    .with_children(|button| {
+                        button
+                            .spawn(TextBundle::from_section("", text_style.clone()))
+                            .insert(SettingText(setting));
+                    });
+            }
+
+            parent
+                .spawn(button_bundle.clone())
+                .insert(ControlsMenuButton::Close)
+                .with_children(|button| {
+                    button.spawn(TextBundle::from_section("Close", text_style.clone()));
+                });
+
+            parent.spawn(TextBundle::from_section(
+                "Click an action, then press a key, mouse button or gamepad button. Delete clears its mouse buttons. Escape cancels, F1 closes.",
+                TextStyle {
+                    font_size: 14.0,
+                    ..text_style.clone()
+                },
+            ));
+        });
+}
+
+fn toggle_controls_menu(keyboard_input: Res<Input<KeyCode>>, mut menu: ResMut<ControlsMenu>) {
+    if keyboard_input.just_pressed(CONTROLS_MENU_KEY) {
+        if menu.open {
+            menu.close();
+        } else {
+            menu.open = true;
+        }
+    }
+}
+
+//While the menu is open it captures the input, so keys and clicks in it do not play the game.
+fn show_controls_menu(
+    menu: Res<ControlsMenu>,
+    mut captured: ResMut<InputCaptured>,
+    mut query_roots: Query<&mut Style, With<ControlsMenuRoot>>,
+) {
+    if !menu.is_changed() || captured.0 == menu.open {
+        return;
+    }
+    captured.0 = menu.open;
+    for mut style in query_roots.iter_mut() {
+        style.display = if menu.open {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
+
+fn handle_binding_buttons(
+    query_buttons: Query<(&Interaction, &BindingButton), Changed<Interaction>>,
+    mut menu: ResMut<ControlsMenu>,
+) {
+    for (interaction, binding_button) in query_buttons.iter() {
+        if *interaction == Interaction::Clicked {
+            menu.waiting_for = Some(binding_button.0);
+            menu.mouse_armed = false;
+        }
+    }
+}
+
+fn handle_menu_buttons(
+    query_buttons: Query<(&Interaction, &ControlsMenuButton), Changed<Interaction>>,
+    mut menu: ResMut<ControlsMenu>,
+    mut bindings: ResMut<ControlBindings>,
+    mut one_switch: ResMut<OneSwitch>,
+) {
+    for (interaction, button) in query_buttons.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button {
+            ControlsMenuButton::WheelPlunger => {
+                bindings.mouse_wheel_plunger = !bindings.mouse_wheel_plunger;
+                bindings.save();
+            }
+            ControlsMenuButton::OneSwitchMode => one_switch.next_mode(),
+            ControlsMenuButton::OneSwitchPlunger => {
+                one_switch.auto_plunger = !one_switch.auto_plunger;
+            }
+            ControlsMenuButton::GameSpeed => one_switch.next_game_speed(),
+            ControlsMenuButton::Close => menu.close(),
+        }
+    }
+}
+
+fn capture_binding_key(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut menu: ResMut<ControlsMenu>,
+    mut bindings: ResMut<ControlBindings>,
+) {
+    let action = match menu.waiting_for {
+        Some(action) => action,
+        None => return,
+    };
+    if let Some(key) = keyboard_input.get_just_pressed().next() {
+        match *key {
+            CONTROLS_MENU_KEY | CANCEL_BINDING_KEY => {}
+            CLEAR_MOUSE_BINDING_KEY => {
+                bindings.unbind_mouse_buttons(action);
+                bindings.save();
+            }
+            key => {
+                bindings.bind_key(action, key);
+                bindings.save();
+            }
+        }
+        menu.waiting_for = None;
+    }
+}
+
+//A click on one of the menu buttons is not taken as a binding, so the menu stays usable while waiting.
+fn capture_binding_mouse_button(
+    mouse_buttons: Res<Input<MouseButton>>,
+    query_buttons: Query<&Interaction, With<Button>>,
+    mut menu: ResMut<ControlsMenu>,
+    mut bindings: ResMut<ControlBindings>,
+) {
+    let action = match menu.waiting_for {
+        Some(action) => action,
+        None => return,
+    };
+    if !menu.mouse_armed {
+        if mouse_buttons.get_pressed().next().is_none() {
+            menu.mouse_armed = true;
+        }
+        return;
+    }
+    if query_buttons
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+    {
+        return;
+    }
+    if let Some(button) = mouse_buttons.get_just_pressed().next() {
+        bindings.bind_mouse_button(action, *button);
+        bindings.save();
+        menu.waiting_for = None;
+    }
+}
+
+fn capture_binding_gamepad_button(
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut menu: ResMut<ControlsMenu>,
+    mut bindings: ResMut<ControlBindings>,
+) {
+    let action = match menu.waiting_for {
+        Some(action) => action,
+        None => return,
+    };
+    if let Some(button) = gamepad_buttons.get_just_pressed().next() {
+        bindings.bind_gamepad_button(action, button.button_type);
+        bindings.save();
+        menu.waiting_for = None;
+    }
+}
+
+fn update_binding_texts(
+    menu: Res<ControlsMenu>,
+    bindings: Res<ControlBindings>,
+    one_switch: Res<OneSwitch>,
+    mut query_texts: Query<(&mut Text, &BindingText)>,
+    mut query_setting_texts: Query<(&mut Text, &SettingText), Without<BindingText>>,
+    mut query_buttons: Query<(&mut BackgroundColor, &BindingButton)>,
+) {
+    if !menu.is_changed() && !bindings.is_changed() && !one_switch.is_changed() {
+        return;
+    }
+
+    for (mut text, binding_text) in query_texts.iter_mut() {
+        let keys = if menu.waiting_for == Some(binding_text.0) {
+            "press a key or button".to_string()
+        } else {
+            bindings
+                .keys_for(binding_text.0)
+                .map(|key| format!("{:?}", key))
+                .chain(
+                    bindings
+                        .gamepad_buttons_for(binding_text.0)
+                        .map(|button| format!("Pad {:?}", button)),
+                )
+                .chain(
+                    bindings
+                        .mouse_buttons_for(binding_text.0)
+                        .map(|button| format!("Mouse {:?}", button)),
+                )
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        text.sections[0].value = format!("{}: {}", binding_text.0.name(), keys);
+    }
+    let on_off = |on: bool| if on { "on" } else { "off" };
+    for (mut text, setting_text) in query_setting_texts.iter_mut() {
+        text.sections[0].value = match setting_text.0 {
+            ControlsMenuButton::WheelPlunger => {
+                format!(
+                    "Mouse wheel plunger: {}",
+                    on_off(bindings.mouse_wheel_plunger)
+                )
+            }
+            ControlsMenuButton::OneSwitchMode => {
+                if one_switch.enabled {
+                    format!("One switch: {}", one_switch.scheme.name())
+                } else {
+                    "One switch: off".to_string()
+                }
+            }
+            ControlsMenuButton::OneSwitchPlunger => {
+                format!(
+                    "One switch auto plunger (flip both): {}",
+                    on_off(one_switch.auto_plunger)
+                )
+            }
+            ControlsMenuButton::GameSpeed => {
+                format!("One switch game speed: {}x", one_switch.game_speed)
+            }
+            ControlsMenuButton::Close => String::new(),
+        };
+    }
+    for (mut background_color, binding_button) in query_buttons.iter_mut() {
+        *background_color = if menu.waiting_for == Some(binding_button.0) {
+            WAITING_BUTTON_COLOR.into()
+        } else {
+            BUTTON_COLOR.into()
+        };
+    }
+}
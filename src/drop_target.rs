@@ -0,0 +1,322 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::Ball;
+use super::Floor;
+use super::HalfHeight;
+
+use super::common;
+
+pub struct DropTargetPlugin;
+
+impl Plugin for DropTargetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system_to_stage(StartupStage::PostStartup, spawn_drop_target_banks)
+            .add_system(handle_drop_target_hits)
+            .add_system(update_drop_target_banks.after(handle_drop_target_hits))
+            .add_system(animate_drop_targets);
+    }
+}
+
+//Seconds a completed bank stays down before it comes back up, with the OnCompletion rule.
+const DROP_TARGET_RESET_DELAY: f64 = 1.0;
+//Seconds a target takes to sink below the floor, or to come back up.
+const DROP_TARGET_MOVE_SECONDS: f64 = 0.08;
+
+const DROP_TARGET_WIDTH: f32 = 0.03;
+const DROP_TARGET_THICKNESS: f32 = 0.008;
+const DROP_TARGET_HEIGHT: f32 = 0.03;
+//Space between the targets of a bank.
+const DROP_TARGET_GAP: f32 = 0.006;
+
+const DROP_TARGET_COLOR: Color = Color::ORANGE;
+
+// When a bank of drop targets comes back up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DropTargetResetRule {
+    // All targets come back up a moment after the last one went down.
+    OnCompletion,
+    // All targets come back up this many seconds after the first one went down, whether the bank is complete or not.
+    Timer(f64),
+}
+
+// A row of drop targets. The targets are its children.
+#[derive(Component)]
+pub struct DropTargetBank {
+    pub reset_rule: DropTargetResetRule,
+    targets: Vec<Entity>,
+    // Set when the last target went down, until the bank is reset.
+    complete: bool,
+}
+
+#[derive(Component)]
+pub struct DropTarget {
+    bank: Entity,
+    down: bool,
+    // Game time, in seconds, the target last went down or came up.
+    changed_at: f64,
+    rest_height: f32,
+}
+
+pub fn spawn_drop_target_banks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    query_floors: Query<(Entity, &HalfHeight), With<Floor>>,
+) {
+    //Centre in floor coordinates, number of targets and reset rule of each bank.
+    //The targets stand in a row along x, facing down the table.
+    let banks: [(Vec2, usize, DropTargetResetRule); 2] = [
+        (
+            Vec2::new(-0.22, -0.13),
+            3,
+            DropTargetResetRule::OnCompletion,
+        ),
+        (Vec2::new(0.22, -0.3), 3, DropTargetResetRule::Timer(20.0)),
+    ];
+
+    let mut floor = None;
+    let mut floor_half_height = 0.0;
+    for (entity, half_height) in query_floors.iter() {
+        floor = Some(entity);
+        floor_half_height = half_height.0;
+    }
+
+    let drop_target_mesh_handle = meshes.add(Mesh::from(shape::Box::new(
+        DROP_TARGET_WIDTH,
+        DROP_TARGET_THICKNESS,
+        DROP_TARGET_HEIGHT,
+    )));
+    let material_drop_target = materials.add(DROP_TARGET_COLOR.into());
+    let rest_height = floor_half_height + DROP_TARGET_HEIGHT / 2.0;
+
+    for (bank_pos, target_count, reset_rule) in banks {
+        let bank = commands
+            .spawn(SpatialBundle::from_transform(Transform::from_xyz(
+                bank_pos.x, bank_pos.y, 0.0,
+            )))
+            .id();
+
+        let spacing = DROP_TARGET_WIDTH + DROP_TARGET_GAP;
+        let first_x = -spacing * (target_count - 1) as f32 / 2.0;
+        let mut targets = Vec::new();
+        for i in 0..target_count {
+            let target = commands
+                .spawn(PbrBundle {
+                    mesh: drop_target_mesh_handle.clone(),
+                    material: material_drop_target.clone(),
+                    ..default()
+                })
+                .insert(RigidBody::Fixed)
+                .insert(Collider::cuboid(
+                    DROP_TARGET_WIDTH / 2.0,
+                    DROP_TARGET_THICKNESS / 2.0,
+                    DROP_TARGET_HEIGHT / 2.0,
+                ))
+                .insert(CollisionGroups {
+                    memberships: Group::GROUP_2,
+                    filters: Group::GROUP_3,
+                })
+                .insert(TransformBundle::from(Transform::from_xyz(
+                    first_x + spacing * i as f32,
+                    0.0,
+                    rest_height,
+                )))
+                .insert(DropTarget {
+                    bank,
+                    down: false,
+                    changed_at: f64::NEG_INFINITY,
+                    rest_height,
+                })
+                .id();
+            targets.push(target);
+        }
+
+        commands
+            .entity(bank)
+            .push_children(&targets)
+            .insert(DropTargetBank {
+                reset_rule,
+                targets,
+                complete: false,
+            });
+        commands.entity(floor.unwrap()).add_child(bank);
+    }
+}
+
+//A target hit by a ball goes down. Collision groups with no filters switch its collider off.
+fn handle_drop_target_hits(
+    time: Res<Time>,
+    mut contact_events: EventReader<CollisionEvent>,
+    mut query_drop_targets: Query<(&mut DropTarget, &mut CollisionGroups)>,
+    query_balls: Query<Entity, With<Ball>>,
+    mut drop_target_downs: EventWriter<common::DropTargetDown>,
+) {
+    for contact_event in contact_events.iter() {
+        if let CollisionEvent::Started(h1, h2, _event_flag) = contact_event {
+            for (entity_target, entity_ball) in [(h1, h2), (h2, h1)] {
+                if !query_balls.contains(*entity_ball) {
+                    continue;
+                }
+                let (mut drop_target, mut collision_groups) =
+                    match query_drop_targets.get_mut(*entity_target) {
+                        Ok(drop_target) => drop_target,
+                        Err(_) => continue,
+                    };
+                if drop_target.down {
+                    continue;
+                }
+
+                drop_target.down = true;
+                drop_target.changed_at = time.elapsed_seconds_f64();
+                collision_groups.filters = Group::NONE;
+                drop_target_downs.send(common::DropTargetDown {
+                    bank: drop_target.bank,
+                    target: *entity_target,
+                    ball: *entity_ball,
+                });
+            }
+        }
+    }
+}
+
+//Reports banks that are complete, and brings the targets of a bank back up by its reset rule.
+fn update_drop_target_banks(
+    time: Res<Time>,
+    mut query_banks: Query<(Entity, &mut DropTargetBank)>,
+    mut query_drop_targets: Query<(&mut DropTarget, &mut CollisionGroups)>,
+    mut bank_completes: EventWriter<common::DropTargetBankComplete>,
+) {
+    let now = time.elapsed_seconds_f64();
+    for (entity_bank, mut bank) in query_banks.iter_mut() {
+        let down_at: Vec<Option<f64>> = query_drop_targets
+            .iter_many(&bank.targets)
+            .map(|(drop_target, _collision_groups)| {
+                if drop_target.down {
+                    Some(drop_target.changed_at)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        let (all_down, reset) = bank_progress(bank.reset_rule, &down_at, now);
+
+        if all_down && !bank.complete {
+            bank.complete = true;
+            bank_completes.send(common::DropTargetBankComplete { bank: entity_bank });
+        }
+        if !reset {
+            continue;
+        }
+
+        bank.complete = false;
+        let mut drop_targets = query_drop_targets.iter_many_mut(&bank.targets);
+        while let Some((mut drop_target, mut collision_groups)) = drop_targets.fetch_next() {
+            if drop_target.down {
+                drop_target.down = false;
+                drop_target.changed_at = now;
+                collision_groups.filters = Group::GROUP_3;
+            }
+        }
+    }
+}
+
+//Whether all targets of a bank are down, and whether the bank comes back up now, from the game
+//time each target went down at, None for a target that is up.
+fn bank_progress(
+    reset_rule: DropTargetResetRule,
+    down_at: &[Option<f64>],
+    now: f64,
+) -> (bool, bool) {
+    let down: Vec<f64> = down_at.iter().flatten().copied().collect();
+    if down.is_empty() {
+        return (false, false);
+    }
+    let all_down = down.len() == down_at.len();
+    let first_down_at = down.iter().copied().fold(f64::INFINITY, f64::min);
+    let last_down_at = down.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    let reset = match reset_rule {
+        DropTargetResetRule::OnCompletion => {
+            all_down && now - last_down_at >= DROP_TARGET_RESET_DELAY
+        }
+        DropTargetResetRule::Timer(seconds) => now - first_down_at >= seconds,
+    };
+    (all_down, reset)
+}
+
+//Targets sink below the floor when they go down, and rise back up when the bank resets.
+fn animate_drop_targets(
+    time: Res<Time>,
+    mut query_drop_targets: Query<(&DropTarget, &mut Transform)>,
+) {
+    for (drop_target, mut transform) in query_drop_targets.iter_mut() {
+        let progress = ((time.elapsed_seconds_f64() - drop_target.changed_at)
+            / DROP_TARGET_MOVE_SECONDS)
+            .clamp(0.0, 1.0) as f32;
+        let sunk = if drop_target.down {
+            progress
+        } else {
+            1.0 - progress
+        };
+
+        let height = drop_target.rest_height - (DROP_TARGET_HEIGHT + 0.002) * sunk;
+        if transform.translation.z != height {
+            transform.translation.z = height;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bank_with_targets_up_is_not_complete() {
+        for reset_rule in [
+            DropTargetResetRule::OnCompletion,
+            DropTargetResetRule::Timer(5.0),
+        ] {
+            assert_eq!(
+                bank_progress(reset_rule, &[None, None, None], 10.0),
+                (false, false)
+            );
+            assert_eq!(
+                bank_progress(reset_rule, &[Some(1.0), None, Some(2.0)], 3.0),
+                (false, false)
+            );
+        }
+    }
+
+    #[test]
+    fn completed_bank_resets_after_the_delay() {
+        let down_at = [Some(1.0), Some(3.0), Some(2.0)];
+        let rule = DropTargetResetRule::OnCompletion;
+        assert_eq!(bank_progress(rule, &down_at, 3.0), (true, false));
+        assert_eq!(
+            bank_progress(rule, &down_at, 3.0 + DROP_TARGET_RESET_DELAY / 2.0),
+            (true, false)
+        );
+        assert_eq!(
+            bank_progress(rule, &down_at, 3.0 + DROP_TARGET_RESET_DELAY),
+            (true, true)
+        );
+    }
+
+    #[test]
+    fn incomplete_bank_stays_down_without_a_timer() {
+        let down_at = [Some(1.0), None];
+        assert_eq!(
+            bank_progress(DropTargetResetRule::OnCompletion, &down_at, 100.0),
+            (false, false)
+        );
+    }
+
+    #[test]
+    fn timer_resets_from_the_first_target_down() {
+        let rule = DropTargetResetRule::Timer(5.0);
+        let down_at = [Some(1.0), None, Some(4.0)];
+        assert_eq!(bank_progress(rule, &down_at, 5.9), (false, false));
+        assert_eq!(bank_progress(rule, &down_at, 6.0), (false, true));
+    }
+}
@@ -0,0 +1,324 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::Ball;
+use super::Flipper;
+use super::{FlipperPolicy, Simulation, SIMULATION_DT};
+use super::{InputAction, VirtualActions};
+
+use super::common;
+
+pub const DEFAULT_ENVIRONMENT_PORT: u16 = 5555;
+
+pub struct EnvironmentConfig {
+    // Physics timestep of one tick.
+    pub dt: f32,
+    // Ticks simulated for every step. The action is held for all of them.
+    pub ticks_per_step: u32,
+    // An episode ends after this many ticks, if it has not ended already.
+    pub max_episode_ticks: u64,
+    // Runs the physics with the variable timestep of the game, as at a frame rate of 1 / dt,
+    // instead of a fixed timestep.
+    pub game_timestep: bool,
+    // Every tick lasts dt, give or take up to this share of it, like the uneven frames of the
+    // game. Chosen from the reset seed, so episodes still repeat. Used with game_timestep.
+    pub frame_time_jitter: f32,
+}
+
+impl Default for EnvironmentConfig {
+    fn default() -> Self {
+        EnvironmentConfig {
+            dt: SIMULATION_DT,
+            ticks_per_step: 4,
+            max_episode_ticks: 10 * 60 * 60,
+            game_timestep: false,
+            frame_time_jitter: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Action {
+    #[serde(default)]
+    pub left: bool,
+    #[serde(default)]
+    pub right: bool,
+    #[serde(default)]
+    pub launch: bool,
+    // Nudge direction in the floor plane, length 1 is a normal nudge.
+    #[serde(default)]
+    pub nudge: Option<[f32; 2]>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BallState {
+    pub position: [f32; 3],
+    pub velocity: [f32; 3],
+}
+
+// Ball positions and velocities are in world coordinates.
+// The left flipper angle goes from -0.3 at rest up to 0.3, the right flipper angle from 0.3 at rest down to -0.3.
+#[derive(Clone, Debug, Serialize)]
+pub struct Observation {
+    pub tick: u64,
+    pub balls: Vec<BallState>,
+    pub left_flipper_angle: f32,
+    pub right_flipper_angle: f32,
+    pub score: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StepResult {
+    pub observation: Observation,
+    // Score gained during the step.
+    pub reward: f64,
+    pub done: bool,
+}
+
+// The table as a learning environment. Runs headless with a fixed timestep, so the same
+// actions from the same reset give the same episode.
+pub struct PinballEnvironment {
+    config: EnvironmentConfig,
+    simulation: Simulation,
+    last_score: u64,
+    frame_time_rng: StdRng,
+}
+
+impl PinballEnvironment {
+    pub fn new(config: EnvironmentConfig) -> Self {
+        let simulation = new_table(&config, 0);
+        PinballEnvironment {
+            config,
+            simulation,
+            last_score: 0,
+            frame_time_rng: StdRng::seed_from_u64(0),
+        }
+    }
+
+    //Starts a new episode on a new table. The seed drives everything left to chance in the game,
+    //so the same seed and the same actions give the same episode.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.simulation = new_table(&self.config, seed);
+        self.last_score = 0;
+        self.frame_time_rng = StdRng::seed_from_u64(seed);
+        self.observe()
+    }
+
+    pub fn step(&mut self, action: &Action) -> StepResult {
+        let world = &mut self.simulation.app.world;
+        let mut virtual_actions = world.resource_mut::<VirtualActions>();
+        virtual_actions.set(InputAction::LeftFlipper, action.left);
+        virtual_actions.set(InputAction::RightFlipper, action.right);
+        virtual_actions.set(InputAction::Launch, action.launch);
+        if let Some(nudge) = action.nudge {
+            world.send_event(common::Nudge {
+                direction: Vec2::from(nudge),
+            });
+        }
+
+        for _ in 0..self.config.ticks_per_step {
+            let jitter = self.config.frame_time_jitter;
+            if jitter > 0.0 {
+                let frame_time =
+                    self.config.dt * (1.0 + self.frame_time_rng.gen_range(-jitter..=jitter));
+                self.simulation.step_by(frame_time);
+            } else {
+                self.simulation.step();
+            }
+            if self.is_done() {
+                break;
+            }
+        }
+
+        let observation = self.observe();
+        let reward = (observation.score - self.last_score) as f64;
+        self.last_score = observation.score;
+        StepResult {
+            observation,
+            reward,
+            done: self.is_done(),
+        }
+    }
+
+    //Seconds of game time since the reset.
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.simulation.elapsed_seconds()
+    }
+
+    pub fn observe(&mut self) -> Observation {
+        let tick = self.simulation.tick();
+        let world = &mut self.simulation.app.world;
+
+        let balls = world
+            .query_filtered::<(&Transform, &Velocity), With<Ball>>()
+            .iter(world)
+            .map(|(ball_transform, ball_velocity)| BallState {
+                position: ball_transform.translation.to_array(),
+                velocity: ball_velocity.linvel.to_array(),
+            })
+            .collect();
+        let flipper_angle = |world: &mut World, action: InputAction| {
+            world
+                .query::<&Flipper>()
+                .iter(world)
+                .find(|flipper| flipper.action == action)
+                .map(|flipper| flipper.curr_angle)
+                .unwrap_or(0.0)
+        };
+        let left_flipper_angle = flipper_angle(world, InputAction::LeftFlipper);
+        let right_flipper_angle = flipper_angle(world, InputAction::RightFlipper);
+
+        Observation {
+            tick,
+            balls,
+            left_flipper_angle,
+            right_flipper_angle,
+            score: world.resource::<common::Score>().0,
+        }
+    }
+
+    //The game is over when end game has released the collector and the last ball has drained.
+    pub fn is_done(&mut self) -> bool {
+        if self.simulation.tick() >= self.config.max_episode_ticks {
+            return true;
+        }
+        let world = &mut self.simulation.app.world;
+        world.resource::<common::EndGame>().0
+            && world.query_filtered::<(), With<Ball>>().iter(world).count() == 0
+    }
+}
+
+fn new_table(config: &EnvironmentConfig, seed: u64) -> Simulation {
+    let mut simulation = Simulation::new(config.dt, FlipperPolicy::External, seed);
+    if config.game_timestep {
+        simulation.use_game_timestep();
+    }
+    //First tick runs the startup systems that build the table.
+    simulation.step();
+    simulation
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum Request {
+    Reset {
+        #[serde(default)]
+        seed: u64,
+    },
+    Step {
+        #[serde(default)]
+        action: Action,
+    },
+    Observe,
+}
+
+pub fn run_environment_server_command(args: &[String]) {
+    let mut port = DEFAULT_ENVIRONMENT_PORT;
+    let mut config = EnvironmentConfig::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args.next();
+        match arg.as_str() {
+            "--port" => match value.and_then(|value| value.parse::<u16>().ok()) {
+                Some(value) => port = value,
+                None => exit_with_environment_usage(),
+            },
+            "--ticks-per-step" => match value.and_then(|value| value.parse::<u32>().ok()) {
+                Some(value) => config.ticks_per_step = value,
+                None => exit_with_environment_usage(),
+            },
+            _ => exit_with_environment_usage(),
+        }
+    }
+
+    if let Err(error) = serve_environment(port, config) {
+        eprintln!("Environment server stopped: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn exit_with_environment_usage() -> ! {
+    eprintln!("Usage: pinball3d env-server [--port P] [--ticks-per-step N]");
+    std::process::exit(2);
+}
+
+// Serve the environment on a local TCP port, one client at a time. Every request is one line
+// of JSON, {"command": "reset" | "step" | "observe", ...}, and gets one line of JSON back.
+pub fn serve_environment(port: u16, config: EnvironmentConfig) -> std::io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    println!("Pinball3d environment listening on 127.0.0.1:{}", port);
+
+    let mut environment = PinballEnvironment::new(config);
+    for stream in listener.incoming() {
+        if let Err(error) = serve_client(stream?, &mut environment) {
+            eprintln!("Client disconnected: {}", error);
+        }
+    }
+    Ok(())
+}
+
+fn serve_client(stream: TcpStream, environment: &mut PinballEnvironment) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(Request::Reset { seed }) => serde_json::to_string(&environment.reset(seed)),
+            Ok(Request::Step { action }) => serde_json::to_string(&environment.step(&action)),
+            Ok(Request::Observe) => serde_json::to_string(&environment.observe()),
+            Err(error) => serde_json::to_string(&serde_json::json!({ "error": error.to_string() })),
+        }?;
+        writeln!(writer, "{}", response)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_command_round_trips() {
+        let action = Action {
+            left: true,
+            right: false,
+            launch: true,
+            nudge: Some([0.5, -0.25]),
+        };
+        let line = serde_json::json!({ "command": "step", "action": action }).to_string();
+        match serde_json::from_str::<Request>(&line) {
+            Ok(Request::Step { action: parsed }) => {
+                assert!(parsed.left);
+                assert!(!parsed.right);
+                assert!(parsed.launch);
+                assert_eq!(parsed.nudge, Some([0.5, -0.25]));
+            }
+            _ => panic!("{} is not parsed as a step", line),
+        }
+    }
+
+    #[test]
+    fn missing_fields_get_defaults() {
+        match serde_json::from_str::<Request>(r#"{"command": "step"}"#) {
+            Ok(Request::Step { action }) => {
+                assert!(!action.left && !action.right && !action.launch);
+                assert_eq!(action.nudge, None);
+            }
+            _ => panic!("step without action is not parsed"),
+        }
+        match serde_json::from_str::<Request>(r#"{"command": "reset"}"#) {
+            Ok(Request::Reset { seed }) => assert_eq!(seed, 0),
+            _ => panic!("reset without seed is not parsed"),
+        }
+        assert!(serde_json::from_str::<Request>(r#"{"command": "jump"}"#).is_err());
+    }
+}
@@ -1,30 +1,112 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
+use super::common;
 use super::Floor;
 use super::HalfHeight;
+use super::Pinball3DStages;
+use super::{ActionState, InputAction};
 
 pub struct FlipperPlugin;
 
-#[derive(Component)]
-struct LeftFlipper {
-    curr_angle: f32,
+//Distance from flipper pivot to tip.
+pub const FLIPPER_LENGTH: f32 = 0.08;
+
+//The coil accelerates the flipper up to its strength, the top angular speed in radians per second.
+const FLIPPER_COIL_ACCELERATION: f32 = 1500.0;
+const FLIPPER_COIL_STRENGTH: f32 = 18.0;
+//The return spring pulls a released flipper back down.
+const FLIPPER_RETURN_ACCELERATION: f32 = 150.0;
+const FLIPPER_RETURN_SPEED: f32 = 4.2;
+//Share of the angular speed a flipper keeps when it bounces off an end stop.
+const FLIPPER_BOUNCE: f32 = 0.3;
+
+// Which way the flipper points from its pivot. A left flipper points right, towards the middle of the table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlipperSide {
+    Left,
+    Right,
+}
+
+// A flipper swings about its pivot between the rest angle and the active angle while its action is held.
+// Angles are in radians about the floor normal, 0 is pointing straight along the side's direction.
+#[derive(Component, Clone, Debug)]
+pub struct Flipper {
+    pub side: FlipperSide,
+    // Pivot position in floor coordinates.
+    pub pivot: Vec2,
+    pub rest_angle: f32,
+    pub active_angle: f32,
+    // Coil strength, the top angular speed in radians per second.
+    pub strength: f32,
+    pub action: InputAction,
+    pub curr_angle: f32,
+    pub angular_velocity: f32,
+}
+
+impl Flipper {
+    pub fn new(
+        side: FlipperSide,
+        pivot: Vec2,
+        rest_angle: f32,
+        active_angle: f32,
+        action: InputAction,
+    ) -> Self {
+        Flipper {
+            side,
+            pivot,
+            rest_angle,
+            active_angle,
+            strength: FLIPPER_COIL_STRENGTH,
+            action,
+            curr_angle: rest_angle,
+            angular_velocity: 0.0,
+        }
+    }
+
+    //Rotation about the floor normal for an angle. The right flipper is the left flipper turned around.
+    fn rotation(&self, angle: f32) -> Quat {
+        match self.side {
+            FlipperSide::Left => Quat::from_rotation_z(angle),
+            FlipperSide::Right => Quat::from_rotation_z(std::f32::consts::PI + angle),
+        }
+    }
+
+    //Angle of a rotation about the floor normal, the inverse of rotation.
+    fn angle(&self, rotation: Quat) -> f32 {
+        let direction = (self.rotation(0.0).inverse() * rotation) * Vec3::X;
+        direction.y.atan2(direction.x)
+    }
 }
 
-#[derive(Component)]
-struct RightFlipper {
-    curr_angle: f32,
+//The flippers of the table. Add an entry to get another flipper.
+fn table_flippers() -> Vec<Flipper> {
+    vec![
+        Flipper::new(
+            FlipperSide::Left,
+            Vec2::new(-0.1, -0.8),
+            -0.3,
+            0.3,
+            InputAction::LeftFlipper,
+        ),
+        Flipper::new(
+            FlipperSide::Right,
+            Vec2::new(0.1, -0.8),
+            0.3,
+            -0.3,
+            InputAction::RightFlipper,
+        ),
+    ]
 }
 
 impl Plugin for FlipperPlugin {
     fn build(&self, app: &mut App) {
         app.add_startup_system_to_stage(StartupStage::PostStartup, spawn_flippers)
-            .add_system(left_flipper_movement)
-            .add_system(right_flipper_movement);
+            .add_system_to_stage(Pinball3DStages::Kinematics, flipper_movement);
     }
 }
 
-fn spawn_flippers(
+pub fn spawn_flippers(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut materials: ResMut<Assets<StandardMaterial>>,
@@ -36,19 +118,17 @@ fn spawn_flippers(
         floor = Some(entity);
         floor_half_height = half_height.0;
     }
-    let left_flipper_mesh_handle: Handle<Mesh> =
-        asset_server.load("left_flipper.glb#Mesh0/Primitive0");
+    let flipper_mesh_handle: Handle<Mesh> = asset_server.load("left_flipper.glb#Mesh0/Primitive0");
 
     let material = materials.add(Color::YELLOW.into());
 
-    let left_flipper_position = Vec3::new(-0.1, -0.8, 0.01);
-    let right_flipper_position = Vec3::new(0.1, -0.8, floor_half_height);
     let flipper_half_height = 0.05;
 
     let collider_small_cylinder = Collider::round_cylinder(flipper_half_height, 0.007, 0.002);
     let position_small_cylinder = Vec3::new(0.07, 0.0, flipper_half_height + floor_half_height);
     let rotation_small_cylinder = Quat::from_rotation_x(std::f32::consts::PI / 2.0);
 
+    //The box is on the upper side of the flipper, that is the other side of its pivot line on a turned around flipper.
     let collider_upper_box = Collider::cuboid(0.038, 0.007, flipper_half_height);
     let position_upper_box = Vec3::new(0.033, 0.006, flipper_half_height + floor_half_height);
     let rotation_upper_box = Quat::from_rotation_z(-0.12);
@@ -57,129 +137,136 @@ fn spawn_flippers(
     let position_lower_box = Vec3::new(0.033, -0.006, flipper_half_height + floor_half_height);
     let rotation_lower_box = Quat::from_rotation_z(0.12);
 
-    let left_flipper = commands
-        .spawn(PbrBundle {
-            mesh: left_flipper_mesh_handle.clone(),
-            material: material.clone(),
-            ..default()
-        })
-        .insert(RigidBody::KinematicPositionBased)
-        .insert(Sleeping::disabled())
-        .insert(Ccd::enabled())
-        .insert(Friction {
-            coefficient: 0.7,
-            combine_rule: CoefficientCombineRule::Min,
-        })
-        .insert(Collider::compound(vec![
-            (
-                position_small_cylinder,
-                rotation_small_cylinder,
-                collider_small_cylinder.clone(),
-            ),
-            (
+    //Velocities rotate a body about its centre of mass. With the centre of mass at the pivot,
+    //the angular velocity alone swings the flipper.
+    let pivot_mass_properties = ColliderMassProperties::MassProperties(MassProperties {
+        local_center_of_mass: Vec3::ZERO,
+        mass: 0.05,
+        principal_inertia: Vec3::splat(0.0001),
+        ..default()
+    });
+
+    for flipper in table_flippers() {
+        let box_collider = match flipper.side {
+            FlipperSide::Left => (
                 position_upper_box,
                 rotation_upper_box,
                 collider_upper_box.clone(),
             ),
-        ]))
-        .insert(CollisionGroups {
-            memberships: Group::GROUP_2,
-            filters: Group::GROUP_3,
-        })
-        .insert(TransformBundle::from(Transform::from_xyz(
-            left_flipper_position.x,
-            left_flipper_position.y,
-            left_flipper_position.z,
-        )))
-        .insert(LeftFlipper { curr_angle: 0.0 })
-        .id();
-
-    commands.entity(floor.unwrap()).add_child(left_flipper);
-
-    let right_flipper = commands
-        .spawn(PbrBundle {
-            mesh: left_flipper_mesh_handle.clone(),
-            material: material.clone(),
-            ..default()
-        })
-        .insert(RigidBody::KinematicPositionBased)
-        .insert(Sleeping::disabled())
-        .insert(Ccd::enabled())
-        .insert(Friction {
-            coefficient: 0.7,
-            combine_rule: CoefficientCombineRule::Min,
-        })
-        .insert(Collider::compound(vec![
-            (
-                position_small_cylinder,
-                rotation_small_cylinder,
-                collider_small_cylinder.clone(),
-            ),
-            (
+            FlipperSide::Right => (
                 position_lower_box,
                 rotation_lower_box,
                 collider_lower_box.clone(),
             ),
-        ]))
-        .insert(TransformBundle::from(Transform {
-            translation: Vec3::new(
-                right_flipper_position.x,
-                right_flipper_position.y,
-                right_flipper_position.z,
-            ),
-            rotation: Quat::from_rotation_z(-std::f32::consts::PI),
-            ..default()
-        }))
-        .insert(CollisionGroups {
-            memberships: Group::GROUP_2,
-            filters: Group::GROUP_3,
-        })
-        .insert(RightFlipper { curr_angle: 0.0 })
-        .id();
-
-    commands.entity(floor.unwrap()).add_child(right_flipper);
+        };
+
+        let flipper_entity = commands
+            .spawn(PbrBundle {
+                mesh: flipper_mesh_handle.clone(),
+                material: material.clone(),
+                ..default()
+            })
+            .insert(RigidBody::KinematicVelocityBased)
+            .insert(Velocity::zero())
+            .insert(Sleeping::disabled())
+            .insert(Ccd::enabled())
+            .insert(Friction {
+                coefficient: 0.7,
+                combine_rule: CoefficientCombineRule::Min,
+            })
+            .insert(pivot_mass_properties)
+            .insert(Collider::compound(vec![
+                (
+                    position_small_cylinder,
+                    rotation_small_cylinder,
+                    collider_small_cylinder.clone(),
+                ),
+                box_collider,
+            ]))
+            .insert(CollisionGroups {
+                memberships: Group::GROUP_2,
+                filters: Group::GROUP_3,
+            })
+            .insert(TransformBundle::from(Transform {
+                translation: Vec3::new(flipper.pivot.x, flipper.pivot.y, floor_half_height),
+                rotation: flipper.rotation(flipper.curr_angle),
+                ..default()
+            }))
+            .insert(flipper)
+            .id();
+
+        commands.entity(floor.unwrap()).add_child(flipper_entity);
+    }
 }
 
-fn left_flipper_movement(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut left_flippers: Query<(&mut LeftFlipper, &mut Transform), With<LeftFlipper>>,
-) {
-    for (mut left_flipper, mut left_flipper_transform) in left_flippers.iter_mut() {
-        let mut new_angle = left_flipper.curr_angle;
-        let change_angle: f32;
-
-        if keyboard_input.pressed(KeyCode::Left) {
-            change_angle = 0.3;
-        } else {
-            change_angle = -0.07;
-        }
+//One physics step of a flipper, with the stroke angle and angular velocity positive towards
+//the active angle. Returns the new stroke angle and angular velocity.
+fn flipper_stroke(
+    stroke_angle: f32,
+    angular_velocity: f32,
+    stroke_length: f32,
+    strength: f32,
+    pressed: bool,
+    dt: f32,
+) -> (f32, f32) {
+    let (target_velocity, acceleration) = if pressed {
+        (strength, FLIPPER_COIL_ACCELERATION)
+    } else {
+        (-FLIPPER_RETURN_SPEED, FLIPPER_RETURN_ACCELERATION)
+    };
+    let max_change = acceleration * dt;
+    let mut new_velocity =
+        angular_velocity + (target_velocity - angular_velocity).clamp(-max_change, max_change);
+    let mut new_angle = stroke_angle + new_velocity * dt;
 
-        new_angle += change_angle;
-        let new_clamped_angle = new_angle.clamp(-0.3, 0.3);
-        let pivot_rotation = Quat::from_rotation_z(new_clamped_angle - left_flipper.curr_angle);
-        left_flipper_transform.rotate_local(pivot_rotation);
-        left_flipper.curr_angle = new_clamped_angle;
+    if new_angle > stroke_length {
+        new_angle = stroke_length;
+        new_velocity = -new_velocity * FLIPPER_BOUNCE;
+    } else if new_angle < 0.0 {
+        new_angle = 0.0;
+        new_velocity = -new_velocity * FLIPPER_BOUNCE;
     }
+    (new_angle, new_velocity)
 }
 
-fn right_flipper_movement(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut right_flippers: Query<(&mut RightFlipper, &mut Transform), With<RightFlipper>>,
-) {
-    for (mut right_flipper, mut right_flipper_transform) in right_flippers.iter_mut() {
-        let mut new_angle = right_flipper.curr_angle;
-        let change_angle: f32;
-
-        if keyboard_input.pressed(KeyCode::Right) {
-            change_angle = -0.3;
-        } else {
-            change_angle = 0.07;
-        }
+//Angular velocity, in world coordinates, that takes a flipper from one angle to the next in one physics step.
+//The ball is hit with the speed the flipper really has, whatever the frame rate.
+fn flipper_angvel(floor_transform: &GlobalTransform, angle: f32, new_angle: f32, dt: f32) -> Vec3 {
+    if dt <= 0.0 {
+        return Vec3::ZERO;
+    }
+    let (_scale, floor_rotation, _translation) = floor_transform.to_scale_rotation_translation();
+    floor_rotation * Vec3::Z * (new_angle - angle) / dt
+}
 
-        new_angle += change_angle;
-        let new_clamped_angle = new_angle.clamp(-0.3, 0.3);
-        let pivot_rotation = Quat::from_rotation_z(new_clamped_angle - right_flipper.curr_angle);
-        right_flipper_transform.rotate_local(pivot_rotation);
-        right_flipper.curr_angle = new_clamped_angle;
+fn flipper_movement(
+    time: Res<Time>,
+    rapier_config: Res<RapierConfiguration>,
+    action_state: Res<ActionState>,
+    query_floors: Query<&GlobalTransform, With<Floor>>,
+    mut flippers: Query<(&mut Flipper, &Transform, &mut Velocity)>,
+) {
+    let dt = common::physics_dt(&rapier_config, &time);
+    let floor_transform = match query_floors.iter().next() {
+        Some(floor_transform) => floor_transform,
+        None => return,
+    };
+    for (mut flipper, flipper_transform, mut flipper_velocity) in flippers.iter_mut() {
+        //Start from where the physics really put the flipper, so the angle never drifts from the body.
+        flipper.curr_angle = flipper.angle(flipper_transform.rotation);
+        let direction = (flipper.active_angle - flipper.rest_angle).signum();
+        let (new_stroke_angle, new_stroke_velocity) = flipper_stroke(
+            (flipper.curr_angle - flipper.rest_angle) * direction,
+            flipper.angular_velocity * direction,
+            (flipper.active_angle - flipper.rest_angle).abs(),
+            flipper.strength,
+            action_state.pressed(flipper.action),
+            dt,
+        );
+        let new_angle = flipper.rest_angle + new_stroke_angle * direction;
+        flipper_velocity.angvel =
+            flipper_angvel(floor_transform, flipper.curr_angle, new_angle, dt);
+        flipper.curr_angle = new_angle;
+        flipper.angular_velocity = new_stroke_velocity * direction;
     }
 }
@@ -0,0 +1,130 @@
+use bevy::input::gamepad::{GamepadEvent, GamepadEventType};
+use bevy::prelude::*;
+
+use super::{ActionState, ActionSystem, ControlBindings, DeviceActionSystem, InputAction};
+
+pub struct GamepadPlugin;
+
+impl Plugin for GamepadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerGamepads>()
+            .add_system_to_stage(CoreStage::PreUpdate, assign_gamepads.before(ActionSystem))
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                gamepad_actions
+                    .label(DeviceActionSystem)
+                    .after(ActionSystem),
+            );
+    }
+}
+
+//Pads used at once, one per player. All of them drive the same table.
+pub const MAX_PLAYERS: usize = 2;
+
+//Left stick pulled down further than this pulls the plunger, fully down pulls it all the way.
+const PLUNGER_DEADZONE: f32 = 0.1;
+//A stick springing back loses at least this much pull in one frame. That fires the plunger
+//with the pull it had, instead of letting it follow the stick on the way up.
+const PLUNGER_RELEASE_DROP: f32 = 0.25;
+//Right stick deflection that nudges the table.
+const NUDGE_DEADZONE: f32 = 0.6;
+
+// Connected pads, in the order they were plugged in. A pad that is unplugged leaves its
+// slot free, so the other player keeps theirs.
+#[derive(Resource, Default)]
+pub struct PlayerGamepads {
+    pub slots: [Option<Gamepad>; MAX_PLAYERS],
+    plungers: [PlungerStick; MAX_PLAYERS],
+}
+
+#[derive(Clone, Copy, Default)]
+struct PlungerStick {
+    pull: f32,
+    // Let go, waits for the stick to come back to the centre.
+    released: bool,
+}
+
+impl PlayerGamepads {
+    pub fn player(&self, gamepad: Gamepad) -> Option<usize> {
+        self.slots.iter().position(|slot| *slot == Some(gamepad))
+    }
+}
+
+fn assign_gamepads(
+    mut gamepad_events: EventReader<GamepadEvent>,
+    mut player_gamepads: ResMut<PlayerGamepads>,
+) {
+    for event in gamepad_events.iter() {
+        match event.event_type {
+            GamepadEventType::Connected(_) => {
+                if player_gamepads.player(event.gamepad).is_some() {
+                    continue;
+                }
+                match player_gamepads.slots.iter().position(Option::is_none) {
+                    Some(player) => {
+                        player_gamepads.slots[player] = Some(event.gamepad);
+                        player_gamepads.plungers[player] = PlungerStick::default();
+                        info!("Gamepad {} is player {}", event.gamepad.id, player + 1);
+                    }
+                    None => info!("Gamepad {} ignored, all players have one", event.gamepad.id),
+                }
+            }
+            GamepadEventType::Disconnected => {
+                if let Some(player) = player_gamepads.player(event.gamepad) {
+                    player_gamepads.slots[player] = None;
+                    info!("Gamepad of player {} disconnected", player + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn gamepad_actions(
+    bindings: Res<ControlBindings>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut player_gamepads: ResMut<PlayerGamepads>,
+    mut action_state: ResMut<ActionState>,
+) {
+    for player in 0..MAX_PLAYERS {
+        let gamepad = match player_gamepads.slots[player] {
+            Some(gamepad) => gamepad,
+            None => continue,
+        };
+
+        for binding in bindings.gamepad_buttons.iter() {
+            if buttons.pressed(GamepadButton::new(gamepad, binding.button)) {
+                action_state.press(binding.action);
+            }
+        }
+
+        //Plunger: pull the left stick down and let go.
+        let stick_y = axes
+            .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
+            .unwrap_or(0.0);
+        let pull = ((-stick_y - PLUNGER_DEADZONE) / (1.0 - PLUNGER_DEADZONE)).clamp(0.0, 1.0);
+        let plunger = &mut player_gamepads.plungers[player];
+        if pull == 0.0 {
+            plunger.released = false;
+        } else if plunger.pull - pull > PLUNGER_RELEASE_DROP {
+            plunger.released = true;
+        }
+        plunger.pull = pull;
+        if pull > 0.0 && !plunger.released {
+            action_state.pull_plunger(pull);
+        }
+
+        //Nudge in the direction the right stick is pushed.
+        let nudge = Vec2::new(
+            axes.get(GamepadAxis::new(gamepad, GamepadAxisType::RightStickX))
+                .unwrap_or(0.0),
+            axes.get(GamepadAxis::new(gamepad, GamepadAxisType::RightStickY))
+                .unwrap_or(0.0),
+        );
+        if nudge.length() > NUDGE_DEADZONE {
+            action_state.press(InputAction::Nudge);
+            action_state.nudge_direction = nudge.normalize();
+        }
+    }
+}
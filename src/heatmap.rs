@@ -0,0 +1,406 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::Serialize;
+
+use super::ball;
+use super::is_ball_in_star_collector;
+use super::is_ball_on_launcher;
+use super::Ball;
+use super::Floor;
+use super::LEFT_OUTLANE_WALL_X;
+use super::{FlipperPolicy, Simulation, SIMULATION_DT};
+
+use super::common;
+
+//Area of the floor that is binned, in floor coordinates.
+const HEATMAP_MIN: Vec2 = Vec2::new(-0.4, -1.0);
+const HEATMAP_MAX: Vec2 = Vec2::new(0.4, 0.4);
+const HEATMAP_CELL_SIZE: f32 = 0.01;
+//Every cell becomes a square of this many pixels in the png.
+const HEATMAP_PIXELS_PER_CELL: u32 = 4;
+
+//A ball still in play after this many seconds is counted as stuck and replaced.
+const MAX_BALL_LIFETIME_SECONDS: f32 = 120.0;
+
+pub struct HeatmapOptions {
+    pub balls: u32,
+    pub policy: FlipperPolicy,
+    pub seed: u64,
+    pub out_dir: PathBuf,
+}
+
+impl Default for HeatmapOptions {
+    fn default() -> Self {
+        HeatmapOptions {
+            balls: 100,
+            policy: FlipperPolicy::Random,
+            seed: 0,
+            out_dir: PathBuf::from("heatmap"),
+        }
+    }
+}
+
+impl HeatmapOptions {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = HeatmapOptions::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("Missing value for {}", arg))?;
+            match arg.as_str() {
+                "--balls" => {
+                    options.balls = value
+                        .parse()
+                        .map_err(|_| format!("Invalid ball count {}", value))?
+                }
+                "--policy" => {
+                    options.policy = FlipperPolicy::parse(value)
+                        .ok_or_else(|| format!("Unknown flipper policy {}", value))?
+                }
+                "--seed" => {
+                    options.seed = value
+                        .parse()
+                        .map_err(|_| format!("Invalid seed {}", value))?
+                }
+                "--out" => options.out_dir = PathBuf::from(value),
+                _ => return Err(format!("Unknown argument {}", arg)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+#[derive(Default, Clone, Serialize)]
+pub struct DrainCounts {
+    pub left_outlane: u32,
+    pub centre: u32,
+}
+
+#[derive(Serialize)]
+pub struct HeatmapSummary {
+    pub balls: u32,
+    pub policy: String,
+    pub seed: u64,
+    pub ticks: u64,
+    pub simulated_seconds: f32,
+    pub drains: DrainCounts,
+    pub collected: u32,
+    pub stuck: u32,
+    pub average_ball_lifetime_seconds: f32,
+    pub hits_per_element_kind: BTreeMap<String, u32>,
+    pub hits_per_element: BTreeMap<String, u32>,
+    pub heatmap_png: String,
+    pub heatmap_cell_size: f32,
+}
+
+// Statistics collected while the simulation runs.
+#[derive(Resource)]
+struct PlayfieldStats {
+    columns: usize,
+    rows: usize,
+    occupancy: Vec<u32>,
+    drains: DrainCounts,
+    collected: u32,
+    stuck: u32,
+    lifetimes: Vec<f32>,
+    hits_per_element_kind: BTreeMap<String, u32>,
+    hits_per_element: BTreeMap<String, u32>,
+    //Tick each ball in play was first seen.
+    ball_first_tick: HashMap<Entity, u64>,
+    //Collected balls stay on the table, so remember which balls are already counted.
+    finished: HashSet<Entity>,
+    tick: u64,
+}
+
+impl PlayfieldStats {
+    fn new() -> Self {
+        let size = (HEATMAP_MAX - HEATMAP_MIN) / HEATMAP_CELL_SIZE;
+        let columns = size.x.round() as usize;
+        let rows = size.y.round() as usize;
+        PlayfieldStats {
+            columns,
+            rows,
+            occupancy: vec![0; columns * rows],
+            drains: DrainCounts::default(),
+            collected: 0,
+            stuck: 0,
+            lifetimes: Vec::new(),
+            hits_per_element_kind: BTreeMap::new(),
+            hits_per_element: BTreeMap::new(),
+            ball_first_tick: HashMap::new(),
+            finished: HashSet::new(),
+            tick: 0,
+        }
+    }
+
+    fn finished_balls(&self) -> u32 {
+        self.drains.left_outlane + self.drains.centre + self.collected
+    }
+
+    fn finish_ball(&mut self, entity_ball: Entity) -> bool {
+        match self.ball_first_tick.remove(&entity_ball) {
+            Some(first_tick) => {
+                self.finished.insert(entity_ball);
+                self.lifetimes
+                    .push((self.tick - first_tick) as f32 * SIMULATION_DT);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+pub fn run_heatmap_command(args: &[String]) {
+    let options = match HeatmapOptions::parse(args) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            eprintln!(
+                "Usage: pinball3d heatmap [--balls N] [--policy idle|hold|random|autopilot] [--seed S] [--out DIR]"
+            );
+            std::process::exit(2);
+        }
+    };
+
+    match run_heatmap(&options) {
+        Ok(summary) => println!(
+            "Simulated {} balls in {:.0} seconds of play, report written to {}",
+            summary.balls,
+            summary.simulated_seconds,
+            options.out_dir.display()
+        ),
+        Err(error) => {
+            eprintln!("Could not write heatmap report: {}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Run simulate_heatmap and write heatmap.png and summary.json to options.out_dir.
+pub fn run_heatmap(options: &HeatmapOptions) -> Result<HeatmapSummary, Box<dyn std::error::Error>> {
+    let (summary, image) = simulate_heatmap(options);
+
+    std::fs::create_dir_all(&options.out_dir)?;
+    image.save(options.out_dir.join(&summary.heatmap_png))?;
+    std::fs::write(
+        options.out_dir.join("summary.json"),
+        serde_json::to_string_pretty(&summary)?,
+    )?;
+
+    Ok(summary)
+}
+
+// Launch balls until options.balls have drained or been collected, and return the statistics
+// and the occupancy heatmap.
+pub fn simulate_heatmap(options: &HeatmapOptions) -> (HeatmapSummary, image::RgbImage) {
+    let mut simulation = Simulation::new(SIMULATION_DT, options.policy, options.seed);
+    simulation
+        .app
+        .insert_resource(PlayfieldStats::new())
+        //The tick is counted in record_ball_positions, so it runs first.
+        .add_system_to_stage(CoreStage::PostUpdate, record_ball_positions)
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            record_element_hits.after(record_ball_positions),
+        )
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            record_ball_outcomes.after(record_ball_positions),
+        )
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            replace_stuck_balls
+                .after(record_ball_positions)
+                .after(record_ball_outcomes),
+        );
+
+    //When the table reaches end game no new balls are spawned, so also stop when none are left.
+    let max_ticks = (options.balls as f32 * MAX_BALL_LIFETIME_SECONDS / SIMULATION_DT) as u64;
+    let mut ticks_without_balls = 0;
+    while simulation.tick() < max_ticks {
+        simulation.step();
+
+        let world = &mut simulation.app.world;
+        if world.resource::<PlayfieldStats>().finished_balls() >= options.balls {
+            break;
+        }
+        if world.query_filtered::<(), With<Ball>>().iter(world).count() == 0 {
+            ticks_without_balls += 1;
+            if ticks_without_balls > 60 {
+                break;
+            }
+        } else {
+            ticks_without_balls = 0;
+        }
+    }
+
+    let stats = simulation.app.world.resource::<PlayfieldStats>();
+
+    let average_ball_lifetime_seconds = if stats.lifetimes.is_empty() {
+        0.0
+    } else {
+        stats.lifetimes.iter().sum::<f32>() / stats.lifetimes.len() as f32
+    };
+
+    let summary = HeatmapSummary {
+        balls: stats.finished_balls(),
+        policy: options.policy.name().to_string(),
+        seed: options.seed,
+        ticks: simulation.tick(),
+        simulated_seconds: simulation.elapsed_seconds(),
+        drains: stats.drains.clone(),
+        collected: stats.collected,
+        stuck: stats.stuck,
+        average_ball_lifetime_seconds,
+        hits_per_element_kind: stats.hits_per_element_kind.clone(),
+        hits_per_element: stats.hits_per_element.clone(),
+        heatmap_png: "heatmap.png".to_string(),
+        heatmap_cell_size: HEATMAP_CELL_SIZE,
+    };
+
+    (summary, heatmap_image(stats))
+}
+
+//Black for cells never visited, then red, yellow and white on a log scale.
+fn heatmap_image(stats: &PlayfieldStats) -> image::RgbImage {
+    let max_count = stats.occupancy.iter().copied().max().unwrap_or(0).max(1) as f32;
+    let width = stats.columns as u32 * HEATMAP_PIXELS_PER_CELL;
+    let height = stats.rows as u32 * HEATMAP_PIXELS_PER_CELL;
+
+    image::RgbImage::from_fn(width, height, |x, y| {
+        let column = (x / HEATMAP_PIXELS_PER_CELL) as usize;
+        //Image rows go downwards, floor y goes upwards.
+        let row = stats.rows - 1 - (y / HEATMAP_PIXELS_PER_CELL) as usize;
+        let count = stats.occupancy[row * stats.columns + column] as f32;
+        let heat = (1.0 + count).ln() / (1.0 + max_count).ln();
+
+        let red = (heat * 3.0).clamp(0.0, 1.0);
+        let green = (heat * 3.0 - 1.0).clamp(0.0, 1.0);
+        let blue = (heat * 3.0 - 2.0).clamp(0.0, 1.0);
+        image::Rgb([
+            (red * 255.0) as u8,
+            (green * 255.0) as u8,
+            (blue * 255.0) as u8,
+        ])
+    })
+}
+
+//Balls resting in the star collector or waiting on the launcher are not counted, they would
+//swamp the heatmap with two fixed spots.
+fn record_ball_positions(
+    query_balls: Query<(Entity, &Transform, &Velocity, &CollisionGroups), With<Ball>>,
+    query_floors: Query<&GlobalTransform, With<Floor>>,
+    mut stats: ResMut<PlayfieldStats>,
+) {
+    stats.tick += 1;
+    let floor_transform = match query_floors.iter().next() {
+        Some(floor_transform) => floor_transform,
+        None => return,
+    };
+    let world_to_floor = floor_transform.compute_matrix().inverse();
+
+    for (entity_ball, ball_transform, ball_velocity, collision_group) in query_balls.iter() {
+        if !stats.finished.contains(&entity_ball) {
+            let tick = stats.tick;
+            stats.ball_first_tick.entry(entity_ball).or_insert(tick);
+        }
+        if is_ball_in_star_collector(collision_group)
+            || is_ball_on_launcher(ball_transform, ball_velocity)
+        {
+            continue;
+        }
+
+        let floor_position = world_to_floor
+            .transform_point3(ball_transform.translation)
+            .truncate();
+        let cell = ((floor_position - HEATMAP_MIN) / HEATMAP_CELL_SIZE).floor();
+        if cell.x >= 0.0
+            && cell.y >= 0.0
+            && (cell.x as usize) < stats.columns
+            && (cell.y as usize) < stats.rows
+        {
+            let index = cell.y as usize * stats.columns + cell.x as usize;
+            stats.occupancy[index] += 1;
+        }
+    }
+}
+
+fn record_element_hits(
+    mut element_hits: EventReader<common::ElementHit>,
+    query_elements: Query<&Transform>,
+    mut stats: ResMut<PlayfieldStats>,
+) {
+    for hit in element_hits.iter() {
+        *stats
+            .hits_per_element_kind
+            .entry(hit.kind.name().to_string())
+            .or_insert(0) += 1;
+
+        //Elements are named by kind and position on the floor, which stays comparable between runs.
+        if let Ok(element_transform) = query_elements.get(hit.element) {
+            let name = format!(
+                "{} ({:.2}, {:.2})",
+                hit.kind.name(),
+                element_transform.translation.x,
+                element_transform.translation.y
+            );
+            *stats.hits_per_element.entry(name).or_insert(0) += 1;
+        }
+    }
+}
+
+fn record_ball_outcomes(
+    mut ball_drained: EventReader<common::BallDrained>,
+    query_balls: Query<(Entity, &CollisionGroups), With<Ball>>,
+    mut stats: ResMut<PlayfieldStats>,
+) {
+    for drained in ball_drained.iter() {
+        if stats.finish_ball(drained.ball) {
+            //The floor is tilted about the x axis, so world x is floor x. The table has no right
+            //outlane, the right slingshot reaches the launcher wall.
+            if drained.position.x < LEFT_OUTLANE_WALL_X {
+                stats.drains.left_outlane += 1;
+            } else {
+                stats.drains.centre += 1;
+            }
+        }
+    }
+
+    for (entity_ball, collision_group) in query_balls.iter() {
+        if is_ball_in_star_collector(collision_group) && stats.finish_ball(entity_ball) {
+            stats.collected += 1;
+        }
+    }
+}
+
+fn replace_stuck_balls(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    query_balls: Query<(Entity, &ball::MaterialColor), With<Ball>>,
+    mut stats: ResMut<PlayfieldStats>,
+) {
+    for (entity_ball, material_color) in query_balls.iter() {
+        let first_tick = match stats.ball_first_tick.get(&entity_ball) {
+            Some(first_tick) => *first_tick,
+            None => continue,
+        };
+        if (stats.tick - first_tick) as f32 * SIMULATION_DT > MAX_BALL_LIFETIME_SECONDS {
+            stats.ball_first_tick.remove(&entity_ball);
+            stats.finished.insert(entity_ball);
+            stats.stuck += 1;
+            commands.entity(entity_ball).despawn();
+            ball::spawn_single_ball(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &ball::INIT_BALL_POSITION,
+                material_color,
+            );
+        }
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::HashSet;
+
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::common;
+
+pub struct InputActionPlugin;
+
+impl Plugin for InputActionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ControlBindings::load())
+            .init_resource::<ActionState>()
+            .init_resource::<VirtualActions>()
+            .init_resource::<InputCaptured>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                update_action_state.label(ActionSystem).after(InputSystem),
+            );
+    }
+}
+
+//Bindings are read from, and saved to, this file in the working directory.
+pub const CONTROLS_FILE: &str = "controls.json";
+
+// What the player can do. Gameplay systems read these, never raw keys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputAction {
+    LeftFlipper,
+    RightFlipper,
+    Launch,
+    Nudge,
+    Pause,
+    Start,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 6] = [
+        InputAction::LeftFlipper,
+        InputAction::RightFlipper,
+        InputAction::Launch,
+        InputAction::Nudge,
+        InputAction::Pause,
+        InputAction::Start,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            InputAction::LeftFlipper => "Left flipper",
+            InputAction::RightFlipper => "Right flipper",
+            InputAction::Launch => "Launch",
+            InputAction::Nudge => "Nudge",
+            InputAction::Pause => "Pause",
+            InputAction::Start => "Start",
+        }
+    }
+}
+
+//Label for the system that fills ActionState. Devices that add to it run after this.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+pub struct ActionSystem;
+
+//Label for the device systems that add to ActionState, for systems that need all devices read.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+pub struct DeviceActionSystem;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub action: InputAction,
+    pub key: KeyCode,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GamepadButtonBinding {
+    pub action: InputAction,
+    pub button: GamepadButtonType,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MouseButtonBinding {
+    pub action: InputAction,
+    pub button: MouseButton,
+}
+
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct ControlBindings {
+    pub keys: Vec<KeyBinding>,
+    //Files saved before gamepads were supported get the default buttons.
+    #[serde(default = "default_gamepad_buttons")]
+    pub gamepad_buttons: Vec<GamepadButtonBinding>,
+    //Remove a mouse button binding to turn it off.
+    #[serde(default = "default_mouse_buttons")]
+    pub mouse_buttons: Vec<MouseButtonBinding>,
+    // Scrolling the mouse wheel down pulls the plunger back, it fires when the wheel stops.
+    #[serde(default = "default_mouse_wheel_plunger")]
+    pub mouse_wheel_plunger: bool,
+}
+
+//Shoulder buttons and triggers flip, Start pauses and South starts a game.
+fn default_gamepad_buttons() -> Vec<GamepadButtonBinding> {
+    [
+        (InputAction::LeftFlipper, GamepadButtonType::LeftTrigger),
+        (InputAction::LeftFlipper, GamepadButtonType::LeftTrigger2),
+        (InputAction::RightFlipper, GamepadButtonType::RightTrigger),
+        (InputAction::RightFlipper, GamepadButtonType::RightTrigger2),
+        (InputAction::Pause, GamepadButtonType::Start),
+        (InputAction::Start, GamepadButtonType::South),
+    ]
+    .into_iter()
+    .map(|(action, button)| GamepadButtonBinding { action, button })
+    .collect()
+}
+
+//Left and right buttons flip, holding the middle button pulls the plunger.
+fn default_mouse_buttons() -> Vec<MouseButtonBinding> {
+    [
+        (InputAction::LeftFlipper, MouseButton::Left),
+        (InputAction::RightFlipper, MouseButton::Right),
+        (InputAction::Launch, MouseButton::Middle),
+    ]
+    .into_iter()
+    .map(|(action, button)| MouseButtonBinding { action, button })
+    .collect()
+}
+
+fn default_mouse_wheel_plunger() -> bool {
+    true
+}
+
+impl Default for ControlBindings {
+    fn default() -> Self {
+        ControlBindings {
+            keys: vec![
+                KeyBinding {
+                    action: InputAction::LeftFlipper,
+                    key: KeyCode::Left,
+                },
+                KeyBinding {
+                    action: InputAction::RightFlipper,
+                    key: KeyCode::Right,
+                },
+                KeyBinding {
+                    action: InputAction::Launch,
+                    key: KeyCode::Space,
+                },
+                KeyBinding {
+                    action: InputAction::Nudge,
+                    key: KeyCode::Up,
+                },
+                KeyBinding {
+                    action: InputAction::Pause,
+                    key: KeyCode::Escape,
+                },
+                KeyBinding {
+                    action: InputAction::Start,
+                    key: KeyCode::Return,
+                },
+            ],
+            gamepad_buttons: default_gamepad_buttons(),
+            mouse_buttons: default_mouse_buttons(),
+            mouse_wheel_plunger: default_mouse_wheel_plunger(),
+        }
+    }
+}
+
+impl ControlBindings {
+    //Falls back to the default bindings if the file is missing or broken, e.g. in the web build.
+    pub fn load() -> Self {
+        Self::load_from(CONTROLS_FILE)
+    }
+
+    pub fn save(&self) {
+        self.save_to(CONTROLS_FILE);
+    }
+
+    pub fn load_from(path: &str) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save_to(&self, path: &str) {
+        let result = serde_json::to_string_pretty(self)
+            .map_err(|error| error.to_string())
+            .and_then(|json| std::fs::write(path, json).map_err(|error| error.to_string()));
+        if let Err(error) = result {
+            warn!("Could not save controls to {}: {}", path, error);
+        }
+    }
+
+    pub fn keys_for(&self, action: InputAction) -> impl Iterator<Item = KeyCode> + '_ {
+        self.keys
+            .iter()
+            .filter(move |binding| binding.action == action)
+            .map(|binding| binding.key)
+    }
+
+    pub fn gamepad_buttons_for(
+        &self,
+        action: InputAction,
+    ) -> impl Iterator<Item = GamepadButtonType> + '_ {
+        self.gamepad_buttons
+            .iter()
+            .filter(move |binding| binding.action == action)
+            .map(|binding| binding.button)
+    }
+
+    pub fn mouse_buttons_for(&self, action: InputAction) -> impl Iterator<Item = MouseButton> + '_ {
+        self.mouse_buttons
+            .iter()
+            .filter(move |binding| binding.action == action)
+            .map(|binding| binding.button)
+    }
+
+    //Replace all keys bound to the action with this key. The key is taken away from any other
+    //action, so one key never drives two actions.
+    pub fn bind_key(&mut self, action: InputAction, key: KeyCode) {
+        self.keys
+            .retain(|binding| binding.action != action && binding.key != key);
+        self.keys.push(KeyBinding { action, key });
+    }
+
+    //Replace all mouse buttons bound to the action with this button. The button is taken away from
+    //any other action.
+    pub fn bind_mouse_button(&mut self, action: InputAction, button: MouseButton) {
+        self.mouse_buttons
+            .retain(|binding| binding.action != action && binding.button != button);
+        self.mouse_buttons
+            .push(MouseButtonBinding { action, button });
+    }
+
+    //Replace all gamepad buttons bound to the action with this button. The button is taken away
+    //from any other action.
+    pub fn bind_gamepad_button(&mut self, action: InputAction, button: GamepadButtonType) {
+        self.gamepad_buttons
+            .retain(|binding| binding.action != action && binding.button != button);
+        self.gamepad_buttons
+            .push(GamepadButtonBinding { action, button });
+    }
+
+    //The mouse no longer does the action.
+    pub fn unbind_mouse_buttons(&mut self, action: InputAction) {
+        self.mouse_buttons
+            .retain(|binding| binding.action != action);
+    }
+}
+
+// Actions held this frame and the frame before, from every device.
+#[derive(Resource, Default)]
+pub struct ActionState {
+    pressed: HashSet<InputAction>,
+    previous: HashSet<InputAction>,
+    // Direction the table is nudged in, for devices that have one. Zero means straight ahead.
+    pub nudge_direction: Vec2,
+    // How far an analog device pulls the plunger back, 0 to 1. None when no analog device pulls it.
+    pub plunger_pull: Option<f32>,
+    // While paused only Pause and Start are pressed, so the game can be resumed but not played.
+    paused: bool,
+    // While a menu captures the input nothing is pressed.
+    captured: bool,
+}
+
+impl ActionState {
+    pub fn press(&mut self, action: InputAction) {
+        if self.captured {
+            return;
+        }
+        if self.paused && !matches!(action, InputAction::Pause | InputAction::Start) {
+            return;
+        }
+        self.pressed.insert(action);
+    }
+
+    //Takes back a press, for modes that own the input of an action.
+    pub fn release(&mut self, action: InputAction) {
+        self.pressed.remove(&action);
+    }
+
+    //The device pulling furthest wins.
+    pub fn pull_plunger(&mut self, pull: f32) {
+        if self.paused || self.captured {
+            return;
+        }
+        self.plunger_pull = Some(self.plunger_pull.map_or(pull, |current| current.max(pull)));
+    }
+
+    pub fn pressed(&self, action: InputAction) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        self.pressed.contains(&action) && !self.previous.contains(&action)
+    }
+
+    pub fn just_released(&self, action: InputAction) -> bool {
+        !self.pressed.contains(&action) && self.previous.contains(&action)
+    }
+}
+
+// Actions held by software instead of a device: the autopilot, simulations and the learning environment.
+// They stay held until released.
+#[derive(Resource, Default)]
+pub struct VirtualActions(pub HashSet<InputAction>);
+
+impl VirtualActions {
+    pub fn set(&mut self, action: InputAction, pressed: bool) {
+        if pressed {
+            self.0.insert(action);
+        } else {
+            self.0.remove(&action);
+        }
+    }
+}
+
+// True while a menu takes the keys and buttons for itself, e.g. to rebind them.
+// Nothing reaches the game then.
+#[derive(Resource, Default)]
+pub struct InputCaptured(pub bool);
+
+fn update_action_state(
+    keyboard_input: Res<Input<KeyCode>>,
+    paused: Res<common::Paused>,
+    captured: Res<InputCaptured>,
+    bindings: Res<ControlBindings>,
+    virtual_actions: Res<VirtualActions>,
+    mut action_state: ResMut<ActionState>,
+) {
+    let pressed = std::mem::take(&mut action_state.pressed);
+    action_state.previous = pressed;
+    action_state.nudge_direction = Vec2::ZERO;
+    action_state.plunger_pull = None;
+    action_state.paused = paused.0;
+    action_state.captured = captured.0;
+
+    for binding in bindings.keys.iter() {
+        if keyboard_input.pressed(binding.key) {
+            action_state.press(binding.action);
+        }
+    }
+    for action in virtual_actions.0.iter() {
+        action_state.press(*action);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebinding_a_key_takes_it_from_the_other_action() {
+        let mut bindings = ControlBindings::default();
+        bindings.bind_key(InputAction::Launch, KeyCode::Left);
+
+        assert_eq!(
+            bindings.keys_for(InputAction::Launch).collect::<Vec<_>>(),
+            vec![KeyCode::Left]
+        );
+        assert_eq!(bindings.keys_for(InputAction::LeftFlipper).count(), 0);
+    }
+
+    #[test]
+    fn rebinding_buttons_takes_them_from_the_other_action() {
+        let mut bindings = ControlBindings::default();
+        bindings.bind_mouse_button(InputAction::Nudge, MouseButton::Left);
+        bindings.bind_gamepad_button(InputAction::Launch, GamepadButtonType::South);
+
+        assert_eq!(
+            bindings.mouse_buttons_for(InputAction::LeftFlipper).count(),
+            0
+        );
+        assert_eq!(
+            bindings
+                .mouse_buttons_for(InputAction::Nudge)
+                .collect::<Vec<_>>(),
+            vec![MouseButton::Left]
+        );
+        assert_eq!(bindings.gamepad_buttons_for(InputAction::Start).count(), 0);
+        assert_eq!(
+            bindings
+                .gamepad_buttons_for(InputAction::Launch)
+                .collect::<Vec<_>>(),
+            vec![GamepadButtonType::South]
+        );
+    }
+
+    #[test]
+    fn bindings_survive_save_and_load() {
+        let path =
+            std::env::temp_dir().join(format!("pinball3d_controls_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let mut bindings = ControlBindings::default();
+        bindings.bind_key(InputAction::Launch, KeyCode::Down);
+        bindings.unbind_mouse_buttons(InputAction::Launch);
+        bindings.bind_gamepad_button(InputAction::Nudge, GamepadButtonType::North);
+        bindings.mouse_wheel_plunger = false;
+        bindings.save_to(path);
+        let loaded = ControlBindings::load_from(path);
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.keys, bindings.keys);
+        assert_eq!(loaded.gamepad_buttons, bindings.gamepad_buttons);
+        assert_eq!(loaded.mouse_buttons, bindings.mouse_buttons);
+        assert!(!loaded.mouse_wheel_plunger);
+    }
+
+    #[test]
+    fn missing_file_gives_default_bindings() {
+        let loaded = ControlBindings::load_from("no/such/controls.json");
+        assert_eq!(loaded.keys, ControlBindings::default().keys);
+    }
+}
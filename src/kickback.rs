@@ -0,0 +1,274 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::common;
+use super::Ball;
+use super::Floor;
+use super::HalfHeight;
+
+pub struct KickbackPlugin;
+
+impl Plugin for KickbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system_to_stage(StartupStage::PostStartup, spawn_kickbacks)
+            .add_system(light_kickbacks)
+            .add_system(handle_kickback_sensor_events.after(light_kickbacks))
+            .add_system(animate_kickbacks.after(handle_kickback_sensor_events));
+    }
+}
+
+//Impulse that sends a ball from the bottom of the outlane back up the playfield.
+pub const KICKBACK_IMPULSE: f32 = 0.000012;
+//The kicker strikes up this far when it fires, and drops back over the animation time.
+const KICKER_TRAVEL: f32 = 0.02;
+const KICKER_ANIMATION_SECONDS: f64 = 0.15;
+
+const KICKER_COLOR: Color = Color::SILVER;
+const LAMP_COLOR: Color = Color::rgb(0.2, 0.05, 0.05);
+const LAMP_LIT_COLOR: Color = Color::RED;
+
+// Kicker at the bottom of an outlane. When lit, it fires a ball in the outlane back up the
+// playfield and goes out. Completing a drop target bank lights it.
+#[derive(Component)]
+pub struct Kickback {
+    pub lit: bool,
+    // Game time, in seconds, of the last kick.
+    last_fired: f64,
+    rest_translation: Vec3,
+}
+
+impl Kickback {
+    //Fires if lit, and goes out. Returns whether it fired.
+    fn fire(&mut self, now: f64) -> bool {
+        if !self.lit {
+            return false;
+        }
+        self.lit = false;
+        self.last_fired = now;
+        true
+    }
+}
+
+#[derive(Component)]
+struct KickbackSensor {
+    kickback: Entity,
+}
+
+#[derive(Component)]
+struct KickbackLamp {
+    kickback: Entity,
+}
+
+pub fn spawn_kickbacks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    query_floors: Query<(Entity, &HalfHeight), With<Floor>>,
+) {
+    //Bottom of the left outlane, between the left wall and the left outlane wall.
+    let kickbacks_pos: [Vec2; 1] = [Vec2::new(-0.34, -0.93)];
+
+    let mut floor = None;
+    let mut floor_half_height = 0.0;
+    for (entity, half_height) in query_floors.iter() {
+        floor = Some(entity);
+        floor_half_height = half_height.0;
+    }
+
+    let kicker_mesh_handle = meshes.add(Mesh::from(shape::Box::new(0.03, 0.01, 0.02)));
+    let lamp_mesh_handle = meshes.add(Mesh::from(shape::Circle {
+        radius: 0.008,
+        vertices: 24,
+    }));
+    let material_kicker = materials.add(KICKER_COLOR.into());
+
+    for kickback_pos in kickbacks_pos {
+        let kicker_translation = kickback_pos.extend(floor_half_height + 0.01);
+        let kickback = commands
+            .spawn(PbrBundle {
+                mesh: kicker_mesh_handle.clone(),
+                material: material_kicker.clone(),
+                transform: Transform::from_translation(kicker_translation),
+                ..default()
+            })
+            .insert(Kickback {
+                lit: false,
+                last_fired: f64::NEG_INFINITY,
+                rest_translation: kicker_translation,
+            })
+            .id();
+
+        let kickback_sensor = commands
+            .spawn(Collider::cuboid(0.02, 0.02, 0.02))
+            .insert(Sensor)
+            .insert(TransformBundle::from(Transform::from_xyz(
+                kickback_pos.x,
+                kickback_pos.y + 0.03,
+                floor_half_height + 0.02,
+            )))
+            .insert(KickbackSensor { kickback })
+            .id();
+
+        let kickback_lamp = commands
+            .spawn(PbrBundle {
+                mesh: lamp_mesh_handle.clone(),
+                material: materials.add(LAMP_COLOR.into()),
+                transform: Transform::from_xyz(
+                    kickback_pos.x,
+                    kickback_pos.y + 0.1,
+                    floor_half_height + 0.001,
+                ),
+                ..default()
+            })
+            .insert(KickbackLamp { kickback })
+            .id();
+
+        commands
+            .entity(floor.unwrap())
+            .push_children(&[kickback, kickback_sensor, kickback_lamp]);
+    }
+}
+
+//Completing a drop target bank lights every kickback.
+fn light_kickbacks(
+    mut bank_completes: EventReader<common::DropTargetBankComplete>,
+    mut query_kickbacks: Query<&mut Kickback>,
+) {
+    if bank_completes.iter().count() == 0 {
+        return;
+    }
+    for mut kickback in query_kickbacks.iter_mut() {
+        kickback.lit = true;
+    }
+}
+
+//A lit kickback stops the ball in its outlane and fires it straight up the playfield.
+fn handle_kickback_sensor_events(
+    time: Res<Time>,
+    mut contact_events: EventReader<CollisionEvent>,
+    query_sensors: Query<&KickbackSensor>,
+    mut query_kickbacks: Query<&mut Kickback>,
+    query_floors: Query<&GlobalTransform, With<Floor>>,
+    mut query_balls: Query<(&mut ExternalImpulse, &mut Velocity), With<Ball>>,
+    mut element_hits: EventWriter<common::ElementHit>,
+) {
+    for contact_event in contact_events.iter() {
+        if let CollisionEvent::Started(h1, h2, _event_flag) = contact_event {
+            for (entity_sensor, entity_ball) in [(h1, h2), (h2, h1)] {
+                let sensor = match query_sensors.get(*entity_sensor) {
+                    Ok(sensor) => sensor,
+                    Err(_) => continue,
+                };
+                let (mut external_impulse, mut velocity) = match query_balls.get_mut(*entity_ball) {
+                    Ok(ball) => ball,
+                    Err(_) => continue,
+                };
+                let mut kickback = match query_kickbacks.get_mut(sensor.kickback) {
+                    Ok(kickback) => kickback,
+                    Err(_) => continue,
+                };
+                let floor_transform = match query_floors.iter().next() {
+                    Some(floor_transform) => floor_transform,
+                    None => continue,
+                };
+                if !kickback.fire(time.elapsed_seconds_f64()) {
+                    continue;
+                }
+
+                let (_scale, floor_rotation, _translation) =
+                    floor_transform.to_scale_rotation_translation();
+                let impulse = floor_rotation * Vec3::Y * KICKBACK_IMPULSE;
+                velocity.linvel = Vec3::ZERO;
+                external_impulse.impulse += impulse;
+                element_hits.send(common::ElementHit {
+                    kind: common::ElementKind::Kickback,
+                    element: sensor.kickback,
+                    ball: *entity_ball,
+                    impulse,
+                });
+            }
+        }
+    }
+}
+
+//The kicker strikes up when it fires and drops back. The lamp shows whether it is lit.
+fn animate_kickbacks(
+    time: Res<Time>,
+    mut query_kickbacks: Query<(&Kickback, &mut Transform)>,
+    query_lamps: Query<(&KickbackLamp, &Handle<StandardMaterial>)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (kickback, mut transform) in query_kickbacks.iter_mut() {
+        let progress =
+            (time.elapsed_seconds_f64() - kickback.last_fired) / KICKER_ANIMATION_SECONDS;
+        let struck = (1.0 - progress).clamp(0.0, 1.0) as f32;
+        let translation = kickback.rest_translation + Vec3::Y * KICKER_TRAVEL * struck;
+        if transform.translation != translation {
+            transform.translation = translation;
+        }
+    }
+
+    for (lamp, material) in query_lamps.iter() {
+        let lit = match query_kickbacks.get(lamp.kickback) {
+            Ok((kickback, _transform)) => kickback.lit,
+            Err(_) => continue,
+        };
+        let color = if lit { LAMP_LIT_COLOR } else { LAMP_COLOR };
+        if let Some(material) = materials.get(material) {
+            if material.base_color == color {
+                continue;
+            }
+        }
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color = color;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kickback() -> Kickback {
+        Kickback {
+            lit: false,
+            last_fired: f64::NEG_INFINITY,
+            rest_translation: Vec3::ZERO,
+        }
+    }
+
+    #[test]
+    fn unlit_kickback_does_not_fire() {
+        let mut kickback = kickback();
+        assert!(!kickback.fire(1.0));
+        assert_eq!(kickback.last_fired, f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn lit_kickback_fires_once_and_goes_out() {
+        let mut kickback = kickback();
+        kickback.lit = true;
+        assert!(kickback.fire(1.0));
+        assert!(!kickback.lit);
+        assert_eq!(kickback.last_fired, 1.0);
+        assert!(!kickback.fire(1.5));
+    }
+
+    //Completing a bank lights the kickback through DropTargetBankComplete.
+    #[test]
+    fn completed_bank_lights_kickbacks() {
+        let mut world = World::new();
+        world.init_resource::<Events<common::DropTargetBankComplete>>();
+        let entity_kickback = world.spawn(kickback()).id();
+        let mut stage = SystemStage::single(light_kickbacks);
+
+        stage.run(&mut world);
+        assert!(!world.get::<Kickback>(entity_kickback).unwrap().lit);
+
+        world.send_event(common::DropTargetBankComplete {
+            bank: Entity::from_raw(99),
+        });
+        stage.run(&mut world);
+        assert!(world.get::<Kickback>(entity_kickback).unwrap().lit);
+    }
+}
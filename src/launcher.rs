@@ -1,27 +1,48 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
+use super::ball;
+use super::common;
 use super::Ball;
 use super::Floor;
+use super::Pinball3DStages;
+use super::{ActionState, InputAction};
 pub struct LauncherPlugin;
 
 impl Plugin for LauncherPlugin {
     fn build(&self, app: &mut App) {
         app.add_startup_system_to_stage(StartupStage::PostStartup, spawn_launcher_and_gate)
-            .add_system(launcher_movement)
+            .add_system_to_stage(Pinball3DStages::Kinematics, launcher_movement)
             .add_system(handle_gate_sensor_events);
     }
 }
 
+//Balls right of the launcher wall are in the launcher lane.
+pub const LAUNCHER_LANE_MIN_X: f32 = 0.31;
+
+//Holding launch this long pulls the plunger all the way back.
+pub const PLUNGER_FULL_CHARGE_SECONDS: f32 = 1.0;
+//How far back the plunger can be pulled, and how far past its rest position it strikes.
+const PLUNGER_MAX_PULL: f32 = 0.03;
+const PLUNGER_STROKE: f32 = 0.06;
+//Speed of the plunger when it is released, from barely pulled to fully pulled.
+const PLUNGER_MIN_SPEED: f32 = 0.6;
+const PLUNGER_MAX_SPEED: f32 = 2.4;
+const PLUNGER_RETURN_SPEED: f32 = 1.2;
+
 #[derive(Component)]
-struct Launcher {
+pub struct Launcher {
     start_pos: Vec3,
+    // How far the plunger is pulled back, 0 at rest and 1 fully pulled.
+    pub pull: f32,
+    // Speed the plunger strikes up with after release. Zero when not firing.
+    launch_speed: f32,
 }
 
 #[derive(Component)]
 struct GateSensor;
 
-fn spawn_launcher_and_gate(
+pub fn spawn_launcher_and_gate(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
@@ -61,6 +82,8 @@ fn spawn_launcher_and_gate(
         )))
         .insert(Launcher {
             start_pos: launcher_pos,
+            pull: 0.0,
+            launch_speed: 0.0,
         })
         .id();
 
@@ -159,20 +182,49 @@ fn spawn_launcher_and_gate(
     ]);
 }
 
+//True when a ball has rolled down the launcher lane and is waiting to be launched.
+pub fn is_ball_on_launcher(ball_transform: &Transform, ball_velocity: &Velocity) -> bool {
+    ball_transform.translation.x > LAUNCHER_LANE_MIN_X
+        && ball_transform.translation.y < ball::INIT_BALL_POSITION.y
+        && ball_velocity.linvel.length() < 0.05
+}
+
+//The plunger is pulled back while launch is held, or as far as an analog input says,
+//and strikes up when let go. The further it was pulled, the faster it strikes.
 fn launcher_movement(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut launchers: Query<(&mut Launcher, &mut Transform), With<Launcher>>,
+    time: Res<Time>,
+    rapier_config: Res<RapierConfiguration>,
+    action_state: Res<ActionState>,
+    mut launchers: Query<(&mut Launcher, &mut Transform)>,
 ) {
-    for (launcher, mut launcher_transform) in launchers.iter_mut() {
+    let dt = common::physics_dt(&rapier_config, &time);
+    let charging = action_state.pressed(InputAction::Launch) || action_state.plunger_pull.is_some();
+
+    for (mut launcher, mut launcher_transform) in launchers.iter_mut() {
+        let rest_ypos = launcher.start_pos.y;
         let mut next_ypos = launcher_transform.translation.y;
 
-        if keyboard_input.pressed(KeyCode::Space) {
-            next_ypos = next_ypos + 0.03;
+        if launcher.launch_speed > 0.0 {
+            next_ypos += launcher.launch_speed * dt;
+            if next_ypos >= rest_ypos + PLUNGER_STROKE {
+                next_ypos = rest_ypos + PLUNGER_STROKE;
+                launcher.launch_speed = 0.0;
+            }
+        } else if charging {
+            launcher.pull = match action_state.plunger_pull {
+                Some(pull) => pull.clamp(0.0, 1.0),
+                None => (launcher.pull + dt / PLUNGER_FULL_CHARGE_SECONDS).min(1.0),
+            };
+            next_ypos = rest_ypos - launcher.pull * PLUNGER_MAX_PULL;
+        } else if launcher.pull > 0.0 {
+            launcher.launch_speed =
+                PLUNGER_MIN_SPEED + launcher.pull * (PLUNGER_MAX_SPEED - PLUNGER_MIN_SPEED);
+            launcher.pull = 0.0;
         } else {
-            next_ypos = next_ypos - 0.02;
+            next_ypos = (next_ypos - PLUNGER_RETURN_SPEED * dt).max(rest_ypos);
         }
-        let clamped_ypos = next_ypos.clamp(launcher.start_pos.y, launcher.start_pos.y + 0.06);
-        launcher_transform.translation.y = clamped_ypos;
+
+        launcher_transform.translation.y = next_ypos;
     }
 }
 
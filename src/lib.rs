@@ -0,0 +1,208 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+pub mod input;
+use input::*;
+
+pub mod gamepad;
+use gamepad::*;
+
+mod touch;
+use touch::*;
+
+mod mouse;
+use mouse::*;
+
+mod wall;
+use wall::*;
+
+mod flipper;
+use flipper::*;
+
+mod ball;
+use ball::*;
+
+mod launcher;
+use launcher::*;
+
+mod pin;
+use pin::*;
+
+mod bumper;
+use bumper::*;
+
+mod pop_bumper;
+use pop_bumper::*;
+
+mod slingshot;
+use slingshot::*;
+
+mod drop_target;
+use drop_target::*;
+
+mod spinner;
+use spinner::*;
+
+mod rollover;
+use rollover::*;
+
+mod kickback;
+use kickback::*;
+
+mod ramp;
+use ramp::*;
+
+mod saucer;
+use saucer::*;
+
+mod magnet;
+use magnet::*;
+
+mod toy;
+use toy::*;
+
+mod star;
+use star::*;
+
+mod target;
+use target::*;
+
+mod score;
+use score::*;
+
+pub mod telemetry;
+
+pub mod autopilot;
+use autopilot::*;
+
+pub mod one_switch;
+use one_switch::*;
+
+pub mod simulation;
+use simulation::*;
+
+pub mod controls_menu;
+
+pub mod plunger_meter;
+
+pub mod pause;
+
+pub mod heatmap;
+
+pub mod environment;
+
+pub mod regression;
+
+pub mod common;
+
+//This is labels for startup systems. Makes it possible to influence startup system sequence.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+pub enum Pinball3DSystems {
+    Main,
+    Walls,
+    Flippers,
+    Ball,
+}
+
+//Stage right before the physics engine takes in the changes of the frame. Kinematic bodies are
+//moved here, by the time the physics step that follows advances.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, StageLabel)]
+pub enum Pinball3DStages {
+    Kinematics,
+}
+
+//Everything that makes up the table. Shared by the game window and headless simulations.
+pub struct Pinball3DPlugin;
+
+impl Plugin for Pinball3DPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(common::EndGame(false))
+            .insert_resource(common::Score(0))
+            .init_resource::<common::BonusMultiplier>()
+            .init_resource::<common::GameRng>()
+            .insert_resource(common::Paused(false))
+            .add_event::<common::ElementHit>()
+            .add_event::<common::DropTargetDown>()
+            .add_event::<common::DropTargetBankComplete>()
+            .add_event::<common::SpinnerSpin>()
+            .add_event::<common::RolloverLanePassed>()
+            .add_event::<common::RampShot>()
+            .add_event::<common::SaucerHold>()
+            .add_event::<common::BallDrained>()
+            .add_event::<common::Nudge>()
+            //Added before the elements, which put their kinematic systems in a stage before the physics.
+            .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
+            .add_stage_before(
+                PhysicsStages::SyncBackend,
+                Pinball3DStages::Kinematics,
+                SystemStage::parallel(),
+            )
+            .add_plugin(InputActionPlugin)
+            .add_plugin(GamepadPlugin)
+            .add_plugin(TouchControlsPlugin)
+            .add_plugin(MouseControlsPlugin)
+            .add_plugin(WallPlugin)
+            .add_plugin(FlipperPlugin)
+            .add_plugin(BallPlugin)
+            .add_plugin(LauncherPlugin)
+            .add_plugin(PinPlugin)
+            .add_plugin(BumperPlugin)
+            .add_plugin(PopBumperPlugin)
+            .add_plugin(SlingshotPlugin)
+            .add_plugin(DropTargetPlugin)
+            .add_plugin(SpinnerPlugin)
+            .add_plugin(RolloverPlugin)
+            .add_plugin(KickbackPlugin)
+            .add_plugin(RampPlugin)
+            .add_plugin(SaucerPlugin)
+            .add_plugin(MagnetPlugin)
+            .add_plugin(ToyPlugin)
+            .add_plugin(StarPlugin)
+            .add_plugin(TargetPlugin)
+            .add_plugin(ScorePlugin)
+            .add_plugin(AutopilotPlugin)
+            .add_plugin(OneSwitchPlugin)
+            .add_startup_system(setup.label(Pinball3DSystems::Main));
+    }
+}
+
+//Longest time one frame advances the physics. Frames slower than this, below 30 Hz, make the
+//game run slower than real time instead of letting the ball pass through walls.
+pub const MAX_PHYSICS_DT: f32 = 1.0 / 30.0;
+
+fn setup(mut commands: Commands, mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.gravity = Vec3::new(0.0, -0.3, -1.0);
+    rapier_config.timestep_mode = TimestepMode::Variable {
+        max_dt: MAX_PHYSICS_DT,
+        time_scale: 1.0,
+        substeps: 2,
+    };
+
+    // camera and light
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1000.0,
+            shadows_enabled: false,
+            ..default()
+        },
+        transform: Transform::from_xyz(-2.0, 0.0, 5.0),
+        ..default()
+    });
+
+    commands.spawn(PointLightBundle {
+        point_light: PointLight {
+            intensity: 1000.0,
+            shadows_enabled: false,
+            ..default()
+        },
+        transform: Transform::from_xyz(2.0, 0.0, 5.0),
+        ..default()
+    });
+
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, -0.8, 1.8)
+            .looking_at(Vec3::new(0.0, -0.35, 0.0), Vec3::Z), //ok
+        //transform: Transform::from_xyz(0.32, -0.8, 0.1).looking_at(Vec3::new(0.32, -0.3, 0.0), Vec3::Z),
+        ..default()
+    });
+}
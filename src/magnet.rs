@@ -0,0 +1,152 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use rand::Rng;
+
+use super::common;
+use super::Ball;
+use super::BallForceSystem;
+use super::Floor;
+use super::HalfHeight;
+
+pub struct MagnetPlugin;
+
+impl Plugin for MagnetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system_to_stage(StartupStage::PostStartup, spawn_magnets)
+            .add_system(switch_magnets_on_ramp_shots)
+            .add_system(
+                pull_balls_to_magnets
+                    .after(BallForceSystem)
+                    .after(switch_magnets_on_ramp_shots),
+            );
+    }
+}
+
+//A ramp shot switches the magnets on for this many seconds.
+const MAGNET_GRAB_SECONDS: f64 = 3.0;
+//Force against a ball's velocity inside the field, per metre per second, so a grabbed ball settles.
+const MAGNET_DAMPING: f32 = 0.00007;
+
+// Spherical force field under the floor. While on, it pulls balls inside its radius towards
+// its centre, through their ExternalForce.
+#[derive(Component)]
+pub struct Magnet {
+    pub radius: f32,
+    // Pull on a ball at the centre, in newtons. It falls off to nothing at the radius.
+    pub strength: f32,
+    pub on: bool,
+    // Game time, in seconds, the magnet switches itself off.
+    pub off_at: f64,
+    // Impulse given to the balls in the field, in a random direction, when the magnet switches off.
+    // Zero lets the balls go.
+    pub throw_impulse: f32,
+}
+
+impl Magnet {
+    pub fn switch_on(&mut self, now: f64, seconds: f64) {
+        self.on = true;
+        self.off_at = now + seconds;
+    }
+}
+
+pub fn spawn_magnets(
+    mut commands: Commands,
+    query_floors: Query<(Entity, &HalfHeight), With<Floor>>,
+) {
+    //Above the flippers, to grab a ball and throw it out again.
+    let magnets_pos: [Vec2; 1] = [Vec2::new(0.0, -0.6)];
+
+    let mut floor = None;
+    let mut floor_half_height = 0.0;
+    for (entity, half_height) in query_floors.iter() {
+        floor = Some(entity);
+        floor_half_height = half_height.0;
+    }
+
+    for magnet_pos in magnets_pos {
+        let magnet = commands
+            .spawn(TransformBundle::from(Transform::from_xyz(
+                magnet_pos.x,
+                magnet_pos.y,
+                -floor_half_height,
+            )))
+            .insert(Magnet {
+                radius: 0.07,
+                strength: 0.00003,
+                on: false,
+                off_at: f64::NEG_INFINITY,
+                throw_impulse: 0.000006,
+            })
+            .id();
+
+        commands.entity(floor.unwrap()).add_child(magnet);
+    }
+}
+
+//Ramp shots switch the magnets on, to grab the next ball that comes by.
+fn switch_magnets_on_ramp_shots(
+    time: Res<Time>,
+    mut ramp_shots: EventReader<common::RampShot>,
+    mut query_magnets: Query<&mut Magnet>,
+) {
+    if ramp_shots.iter().count() == 0 {
+        return;
+    }
+    for mut magnet in query_magnets.iter_mut() {
+        magnet.switch_on(time.elapsed_seconds_f64(), MAGNET_GRAB_SECONDS);
+    }
+}
+
+//Adds the pull of every magnet that is on to the force on each ball in its field. When a magnet
+//switches off, the balls in its field are thrown out.
+fn pull_balls_to_magnets(
+    time: Res<Time>,
+    mut query_magnets: Query<(&mut Magnet, &GlobalTransform)>,
+    mut query_balls: Query<
+        (
+            &Transform,
+            &Velocity,
+            &mut ExternalForce,
+            &mut ExternalImpulse,
+        ),
+        With<Ball>,
+    >,
+    mut rng: ResMut<common::GameRng>,
+) {
+    let now = time.elapsed_seconds_f64();
+    for (mut magnet, magnet_transform) in query_magnets.iter_mut() {
+        if !magnet.on {
+            continue;
+        }
+        let (_scale, floor_rotation, center) = magnet_transform.to_scale_rotation_translation();
+        let switching_off = now >= magnet.off_at;
+
+        for (ball_transform, velocity, mut external_force, mut external_impulse) in
+            query_balls.iter_mut()
+        {
+            let offset = center - ball_transform.translation;
+            let distance = offset.length();
+            if distance >= magnet.radius {
+                continue;
+            }
+
+            if switching_off {
+                if magnet.throw_impulse > 0.0 {
+                    let angle = rng.0.gen_range(0.0..std::f32::consts::TAU);
+                    let direction = Vec3::new(angle.cos(), angle.sin(), 0.0);
+                    external_impulse.impulse += floor_rotation * direction * magnet.throw_impulse;
+                }
+                continue;
+            }
+
+            let pull =
+                offset.normalize_or_zero() * magnet.strength * (1.0 - distance / magnet.radius);
+            external_force.force += pull - velocity.linvel * MAGNET_DAMPING;
+        }
+
+        if switching_off {
+            magnet.on = false;
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+
+use super::common;
+use super::{ActionState, ActionSystem, ControlBindings, DeviceActionSystem};
+
+pub struct MouseControlsPlugin;
+
+impl Plugin for MouseControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WheelPlunger>().add_system_to_stage(
+            CoreStage::PreUpdate,
+            mouse_actions.label(DeviceActionSystem).after(ActionSystem),
+        );
+    }
+}
+
+//Plunger pull for one line of the mouse wheel. Pixel scrolling, from touchpads, counts this many pixels as a line.
+const WHEEL_PULL_PER_LINE: f32 = 0.1;
+const WHEEL_PIXELS_PER_LINE: f32 = 100.0;
+//The plunger fires when the wheel has been still this long.
+const WHEEL_RELEASE_SECONDS: f32 = 0.2;
+
+#[derive(Resource, Default)]
+pub struct WheelPlunger {
+    pull: f32,
+    still_time: f32,
+}
+
+fn mouse_actions(
+    time: Res<Time>,
+    paused: Res<common::Paused>,
+    bindings: Res<ControlBindings>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut wheel_events: EventReader<MouseWheel>,
+    mut wheel_plunger: ResMut<WheelPlunger>,
+    mut action_state: ResMut<ActionState>,
+) {
+    for binding in bindings.mouse_buttons.iter() {
+        if mouse_buttons.pressed(binding.button) {
+            action_state.press(binding.action);
+        }
+    }
+
+    if !bindings.mouse_wheel_plunger || paused.0 {
+        wheel_events.clear();
+        return;
+    }
+
+    let mut lines = 0.0;
+    for wheel_event in wheel_events.iter() {
+        lines += match wheel_event.unit {
+            MouseScrollUnit::Line => wheel_event.y,
+            MouseScrollUnit::Pixel => wheel_event.y / WHEEL_PIXELS_PER_LINE,
+        };
+    }
+    if lines != 0.0 {
+        //Scrolling down, towards the player, pulls the plunger back.
+        wheel_plunger.pull = (wheel_plunger.pull - lines * WHEEL_PULL_PER_LINE).clamp(0.0, 1.0);
+        wheel_plunger.still_time = 0.0;
+    } else {
+        wheel_plunger.still_time += time.delta_seconds();
+    }
+
+    if wheel_plunger.still_time > WHEEL_RELEASE_SECONDS {
+        wheel_plunger.pull = 0.0;
+    }
+    if wheel_plunger.pull > 0.0 {
+        action_state.pull_plunger(wheel_plunger.pull);
+    }
+}
@@ -0,0 +1,265 @@
+use bevy::prelude::*;
+
+use super::AutopilotAssist;
+use super::{ActionState, ActionSystem, DeviceActionSystem, InputAction, VirtualActions};
+
+pub struct OneSwitchPlugin;
+
+impl Plugin for OneSwitchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OneSwitch>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                apply_one_switch_settings.before(ActionSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                one_switch_actions.after(DeviceActionSystem),
+            );
+    }
+}
+
+// How the one switch plays the flippers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OneSwitchScheme {
+    // The flipper the ball is coming to flips by itself. The switch always works the plunger.
+    AutoFlip,
+    // The switch flips both flippers, and works the plunger unless it fires by itself.
+    FlipBoth,
+}
+
+impl OneSwitchScheme {
+    pub fn parse(name: &str) -> Option<OneSwitchScheme> {
+        match name {
+            "auto-flip" => Some(OneSwitchScheme::AutoFlip),
+            "flip-both" => Some(OneSwitchScheme::FlipBoth),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OneSwitchScheme::AutoFlip => "auto flip",
+            OneSwitchScheme::FlipBoth => "flip both",
+        }
+    }
+}
+
+// Accessibility mode for playing with a single button. Any key or button bound to a flipper
+// or to launch is the switch.
+#[derive(Resource, Clone, Debug)]
+pub struct OneSwitch {
+    pub enabled: bool,
+    pub scheme: OneSwitchScheme,
+    // The plunger fires by itself when a ball is on it. Only with FlipBoth, with AutoFlip the
+    // plunger is all the switch has to do.
+    pub auto_plunger: bool,
+    // Speed of the game, 1 is normal speed. Lower gives more time to react.
+    pub game_speed: f32,
+}
+
+impl Default for OneSwitch {
+    fn default() -> Self {
+        OneSwitch {
+            enabled: false,
+            scheme: OneSwitchScheme::AutoFlip,
+            auto_plunger: true,
+            game_speed: 1.0,
+        }
+    }
+}
+
+//Game speeds the menu steps through.
+pub const ONE_SWITCH_GAME_SPEEDS: [f32; 3] = [1.0, 0.75, 0.5];
+
+impl OneSwitch {
+    //Steps through off, auto flip and flip both, for the menu.
+    pub fn next_mode(&mut self) {
+        match (self.enabled, self.scheme) {
+            (false, _) => {
+                self.enabled = true;
+                self.scheme = OneSwitchScheme::AutoFlip;
+            }
+            (true, OneSwitchScheme::AutoFlip) => self.scheme = OneSwitchScheme::FlipBoth,
+            (true, OneSwitchScheme::FlipBoth) => self.enabled = false,
+        }
+    }
+
+    //Steps to the next slower game speed, and from the slowest back to normal speed.
+    pub fn next_game_speed(&mut self) {
+        self.game_speed = ONE_SWITCH_GAME_SPEEDS
+            .into_iter()
+            .find(|game_speed| *game_speed < self.game_speed)
+            .unwrap_or(ONE_SWITCH_GAME_SPEEDS[0]);
+    }
+
+    //True when the plunger fires by itself, so the switch does not work it.
+    pub fn plunger_fires_by_itself(&self) -> bool {
+        self.auto_plunger && self.scheme == OneSwitchScheme::FlipBoth
+    }
+
+    //Actions the switch gives while it is held. Never empty, there is always something to press.
+    pub fn switch_actions(&self) -> Vec<InputAction> {
+        let mut actions = Vec::new();
+        if self.scheme == OneSwitchScheme::FlipBoth {
+            actions.push(InputAction::LeftFlipper);
+            actions.push(InputAction::RightFlipper);
+        }
+        if !self.plunger_fires_by_itself() {
+            actions.push(InputAction::Launch);
+        }
+        actions
+    }
+
+    //--one-switch turns the mode on, optionally with =auto-flip or =flip-both.
+    //--manual-plunger lets the switch work the plunger with flip-both, --game-speed S slows the game down.
+    pub fn from_args(args: &[String]) -> OneSwitch {
+        let mut one_switch = OneSwitch::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--one-switch" {
+                one_switch.enabled = true;
+            } else if let Some(scheme) = arg.strip_prefix("--one-switch=") {
+                one_switch.enabled = true;
+                match OneSwitchScheme::parse(scheme) {
+                    Some(scheme) => one_switch.scheme = scheme,
+                    None => warn!("Unknown one-switch scheme {}", scheme),
+                }
+            } else if arg == "--manual-plunger" {
+                one_switch.auto_plunger = false;
+            } else if arg == "--game-speed" {
+                match args.next().and_then(|value| value.parse::<f32>().ok()) {
+                    Some(game_speed) if game_speed > 0.0 => one_switch.game_speed = game_speed,
+                    _ => warn!("--game-speed needs a positive number"),
+                }
+            }
+        }
+        one_switch
+    }
+}
+
+//Turns on the autopilot parts the scheme needs, and sets the speed of game time. Physics steps
+//by the scaled frame time, and every timer of the game reads it, so all of the game slows down.
+fn apply_one_switch_settings(
+    one_switch: Res<OneSwitch>,
+    mut assist: ResMut<AutopilotAssist>,
+    mut time: ResMut<Time>,
+) {
+    if !one_switch.is_changed() {
+        return;
+    }
+    assist.flippers = one_switch.enabled && one_switch.scheme == OneSwitchScheme::AutoFlip;
+    assist.launcher = one_switch.enabled && one_switch.plunger_fires_by_itself();
+
+    let game_speed = if one_switch.enabled {
+        one_switch.game_speed
+    } else {
+        1.0
+    };
+    time.set_relative_speed(game_speed);
+}
+
+//Actions held by the autopilot assist are not the player's switch. With AutoFlip the flippers
+//belong to the assist, so the switch does not raise them itself.
+fn one_switch_actions(
+    one_switch: Res<OneSwitch>,
+    virtual_actions: Res<VirtualActions>,
+    mut action_state: ResMut<ActionState>,
+) {
+    if !one_switch.enabled {
+        return;
+    }
+    let switch_pressed = [
+        InputAction::LeftFlipper,
+        InputAction::RightFlipper,
+        InputAction::Launch,
+    ]
+    .into_iter()
+    .any(|action| action_state.pressed(action) && !virtual_actions.0.contains(&action));
+
+    if one_switch.scheme == OneSwitchScheme::AutoFlip {
+        for action in [InputAction::LeftFlipper, InputAction::RightFlipper] {
+            if !virtual_actions.0.contains(&action) {
+                action_state.release(action);
+            }
+        }
+    }
+    if !switch_pressed {
+        return;
+    }
+
+    for action in one_switch.switch_actions() {
+        action_state.press(action);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switch_gives_an_action_in_every_mode() {
+        for scheme in [OneSwitchScheme::AutoFlip, OneSwitchScheme::FlipBoth] {
+            for auto_plunger in [false, true] {
+                let one_switch = OneSwitch {
+                    enabled: true,
+                    scheme,
+                    auto_plunger,
+                    ..Default::default()
+                };
+                assert!(
+                    !one_switch.switch_actions().is_empty(),
+                    "{:?} with auto plunger {}",
+                    scheme,
+                    auto_plunger
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn auto_flip_switch_works_the_plunger() {
+        let one_switch = OneSwitch {
+            enabled: true,
+            scheme: OneSwitchScheme::AutoFlip,
+            auto_plunger: true,
+            ..Default::default()
+        };
+        assert!(!one_switch.plunger_fires_by_itself());
+        assert_eq!(one_switch.switch_actions(), vec![InputAction::Launch]);
+    }
+
+    #[test]
+    fn flip_both_switch_flips_and_launches_unless_auto_plunger() {
+        let mut one_switch = OneSwitch {
+            enabled: true,
+            scheme: OneSwitchScheme::FlipBoth,
+            auto_plunger: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            one_switch.switch_actions(),
+            vec![InputAction::LeftFlipper, InputAction::RightFlipper]
+        );
+        one_switch.auto_plunger = false;
+        assert!(one_switch.switch_actions().contains(&InputAction::Launch));
+    }
+
+    #[test]
+    fn args_set_the_mode() {
+        let args: Vec<String> = [
+            "--one-switch=flip-both",
+            "--manual-plunger",
+            "--game-speed",
+            "0.5",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        let one_switch = OneSwitch::from_args(&args);
+        assert!(one_switch.enabled);
+        assert_eq!(one_switch.scheme, OneSwitchScheme::FlipBoth);
+        assert!(!one_switch.auto_plunger);
+        assert_eq!(one_switch.game_speed, 0.5);
+    }
+}
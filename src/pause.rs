@@ -0,0 +1,240 @@
+use bevy::app::AppExit;
+use bevy::hierarchy::despawn_with_children_recursive;
+use bevy::prelude::*;
+use bevy::window::WindowFocused;
+use bevy_rapier3d::prelude::*;
+
+use super::common;
+use super::controls_menu::ControlsMenu;
+use super::{
+    spawn_balls, spawn_bumpers, spawn_drop_target_banks, spawn_flippers, spawn_kickbacks,
+    spawn_launcher_and_gate, spawn_magnets, spawn_pins, spawn_pop_bumpers, spawn_ramps,
+    spawn_rollover_lanes, spawn_saucers, spawn_slingshots, spawn_spinners, spawn_star,
+    spawn_target, spawn_toys, spawn_walls,
+};
+use super::{ActionState, InputAction};
+use super::{Ball, Floor};
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PauseMenu>()
+            .add_startup_system(spawn_pause_menu)
+            .add_system(toggle_pause)
+            .add_system(pause_on_focus_lost)
+            .add_system(handle_pause_menu_buttons)
+            .add_system(apply_pause.after(toggle_pause).after(pause_on_focus_lost))
+            .add_system(restart_game);
+    }
+}
+
+const BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+
+#[derive(Resource, Default)]
+pub struct PauseMenu {
+    // Set by the restart button, the table is rebuilt at the end of the frame.
+    restart_requested: bool,
+}
+
+#[derive(Component)]
+struct PauseMenuRoot;
+
+#[derive(Component, Clone, Copy)]
+enum PauseMenuButton {
+    Resume,
+    Restart,
+    Controls,
+    Quit,
+}
+
+impl PauseMenuButton {
+    fn name(&self) -> &'static str {
+        match self {
+            PauseMenuButton::Resume => "Resume",
+            PauseMenuButton::Restart => "Restart",
+            PauseMenuButton::Controls => "Controls",
+            PauseMenuButton::Quit => "Quit",
+        }
+    }
+}
+
+fn spawn_pause_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("fonts/DejaVuSans.ttf");
+    let text_style = TextStyle {
+        font: font.clone(),
+        font_size: 18.0,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+            ..default()
+        })
+        .insert(PauseMenuRoot)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Paused",
+                TextStyle {
+                    font_size: 28.0,
+                    ..text_style.clone()
+                },
+            ));
+
+            for button in [
+                PauseMenuButton::Resume,
+                PauseMenuButton::Restart,
+                PauseMenuButton::Controls,
+                PauseMenuButton::Quit,
+            ] {
+                parent
+                    .spawn(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(200.0), Val::Px(36.0)),
+                            margin: UiRect::all(Val::Px(4.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: BUTTON_COLOR.into(),
+                        ..default()
+                    })
+                    .insert(button)
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(button.name(), text_style.clone()));
+                    });
+            }
+        });
+}
+
+fn toggle_pause(action_state: Res<ActionState>, mut paused: ResMut<common::Paused>) {
+    if action_state.just_pressed(InputAction::Pause) {
+        paused.0 = !paused.0;
+    } else if paused.0 && action_state.just_pressed(InputAction::Start) {
+        paused.0 = false;
+    }
+}
+
+fn pause_on_focus_lost(
+    mut focus_events: EventReader<WindowFocused>,
+    mut paused: ResMut<common::Paused>,
+) {
+    for focus_event in focus_events.iter() {
+        if !focus_event.focused {
+            paused.0 = true;
+        }
+    }
+}
+
+fn handle_pause_menu_buttons(
+    query_buttons: Query<(&Interaction, &PauseMenuButton), Changed<Interaction>>,
+    mut paused: ResMut<common::Paused>,
+    mut pause_menu: ResMut<PauseMenu>,
+    mut controls_menu: Option<ResMut<ControlsMenu>>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    for (interaction, button) in query_buttons.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button {
+            PauseMenuButton::Resume => paused.0 = false,
+            PauseMenuButton::Restart => {
+                pause_menu.restart_requested = true;
+                paused.0 = false;
+            }
+            //The way to the controls on devices without F1, like touch screens.
+            PauseMenuButton::Controls => {
+                if let Some(controls_menu) = controls_menu.as_mut() {
+                    controls_menu.open = true;
+                }
+            }
+            PauseMenuButton::Quit => app_exit.send(AppExit),
+        }
+    }
+}
+
+//Stops the physics, and the game clock every timer is based on, and shows the menu. The menu
+//steps aside while the controls menu is open over it.
+fn apply_pause(
+    paused: Res<common::Paused>,
+    controls_menu: Option<Res<ControlsMenu>>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut time: ResMut<Time>,
+    mut query_roots: Query<&mut Style, With<PauseMenuRoot>>,
+) {
+    let controls_open = controls_menu.as_ref().map_or(false, |menu| menu.open);
+    let controls_changed = controls_menu
+        .as_ref()
+        .map_or(false, |menu| menu.is_changed());
+    if !paused.is_changed() && !controls_changed {
+        return;
+    }
+    if paused.is_changed() {
+        rapier_config.physics_pipeline_active = !paused.0;
+        if paused.0 {
+            time.pause();
+        } else {
+            time.unpause();
+        }
+    }
+    for mut style in query_roots.iter_mut() {
+        style.display = if paused.0 && !controls_open {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
+
+//Despawns the table and the balls, and builds them again with the startup systems, in the same order.
+fn restart_game(world: &mut World) {
+    if !world.resource::<PauseMenu>().restart_requested {
+        return;
+    }
+    world.resource_mut::<PauseMenu>().restart_requested = false;
+
+    let table_entities: Vec<Entity> = world
+        .query_filtered::<Entity, Or<(With<Floor>, With<Ball>)>>()
+        .iter(world)
+        .collect();
+    for entity in table_entities {
+        despawn_with_children_recursive(world, entity);
+    }
+    world.resource_mut::<common::Score>().0 = 0;
+    world.resource_mut::<common::BonusMultiplier>().0 = 1;
+    world.resource_mut::<common::EndGame>().0 = false;
+
+    SystemStage::single_threaded()
+        .with_system(spawn_walls)
+        .run(world);
+    SystemStage::single_threaded()
+        .with_system(spawn_flippers)
+        .with_system(spawn_balls)
+        .with_system(spawn_launcher_and_gate)
+        .with_system(spawn_pins)
+        .with_system(spawn_bumpers)
+        .with_system(spawn_pop_bumpers)
+        .with_system(spawn_slingshots)
+        .with_system(spawn_drop_target_banks)
+        .with_system(spawn_spinners)
+        .with_system(spawn_rollover_lanes)
+        .with_system(spawn_kickbacks)
+        .with_system(spawn_star)
+        .with_system(spawn_ramps)
+        .with_system(spawn_saucers)
+        .with_system(spawn_magnets)
+        .with_system(spawn_toys)
+        .with_system(spawn_target)
+        .run(world);
+}
@@ -8,6 +8,8 @@ use rand::Rng;
 use super::Ball;
 use super::Floor;
 
+use super::common;
+
 pub struct PinPlugin;
 
 impl Plugin for PinPlugin {
@@ -17,10 +19,13 @@ impl Plugin for PinPlugin {
     }
 }
 
+//Impulse given to a ball, along its own velocity, when it leaves a pin.
+pub const PIN_PUSH_IMPULSE: f32 = 0.000003;
+
 #[derive(Component)]
 struct Pin;
 
-fn spawn_pins(
+pub fn spawn_pins(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
@@ -106,6 +111,8 @@ fn handle_pin_events(
     mut query_balls: Query<(Entity, &mut ExternalImpulse, &Velocity), With<Ball>>,
     mut contact_events: EventReader<CollisionEvent>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut element_hits: EventWriter<common::ElementHit>,
+    mut rng: ResMut<common::GameRng>,
 ) {
     for contact_event in contact_events.iter() {
         for (entity_pin, _pin, mut material) in query_pins.iter_mut() {
@@ -119,8 +126,7 @@ fn handle_pin_events(
                         Color::GREEN,
                         Color::PINK,
                     ];
-                    let mut rng = rand::thread_rng();
-                    let chosen_index = rng.gen_range(0..5);
+                    let chosen_index = rng.0.gen_range(0..5);
                     let material_pin = materials.add(color_selection[chosen_index].into());
                     *material = material_pin.clone();
                 }
@@ -130,9 +136,15 @@ fn handle_pin_events(
                     //Give ball a push in velocity direction
                     for (entity_ball, mut external_impulse, velocity) in query_balls.iter_mut() {
                         if h1 == &entity_ball || h2 == &entity_ball {
-                            let normalized_velocity = velocity.linvel.normalize();
-                            external_impulse.impulse =
-                                external_impulse.impulse.add(normalized_velocity * 0.000003);
+                            let normalized_velocity = velocity.linvel.normalize_or_zero();
+                            let impulse = normalized_velocity * PIN_PUSH_IMPULSE;
+                            external_impulse.impulse = external_impulse.impulse.add(impulse);
+                            element_hits.send(common::ElementHit {
+                                kind: common::ElementKind::Pin,
+                                element: entity_pin,
+                                ball: entity_ball,
+                                impulse,
+                            });
                         }
                     }
                 }
@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+
+use super::Launcher;
+
+pub struct PlungerMeterPlugin;
+
+impl Plugin for PlungerMeterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(spawn_plunger_meter)
+            .add_system(update_plunger_meter);
+    }
+}
+
+const METER_BACKGROUND_COLOR: Color = Color::rgba(0.15, 0.15, 0.15, 0.6);
+
+#[derive(Component)]
+struct PlungerMeterFill;
+
+//A bar in the lower right corner, next to the launcher lane, that fills as the plunger is pulled.
+fn spawn_plunger_meter(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    right: Val::Px(8.0),
+                    bottom: Val::Px(8.0),
+                    ..default()
+                },
+                size: Size::new(Val::Px(12.0), Val::Px(100.0)),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::FlexEnd,
+                ..default()
+            },
+            background_color: METER_BACKGROUND_COLOR.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Percent(0.0)),
+                        ..default()
+                    },
+                    background_color: Color::GREEN.into(),
+                    ..default()
+                })
+                .insert(PlungerMeterFill);
+        });
+}
+
+//Green when barely pulled, red when fully pulled.
+fn update_plunger_meter(
+    query_launchers: Query<&Launcher, Changed<Launcher>>,
+    mut query_fills: Query<(&mut Style, &mut BackgroundColor), With<PlungerMeterFill>>,
+) {
+    for launcher in query_launchers.iter() {
+        for (mut style, mut background_color) in query_fills.iter_mut() {
+            style.size.height = Val::Percent(launcher.pull * 100.0);
+            *background_color = Color::rgb(launcher.pull, 1.0 - launcher.pull, 0.0).into();
+        }
+    }
+}
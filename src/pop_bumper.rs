@@ -0,0 +1,300 @@
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy_rapier3d::prelude::*;
+
+use super::Ball;
+use super::Floor;
+use super::HalfHeight;
+
+use super::common;
+
+pub struct PopBumperPlugin;
+
+impl Plugin for PopBumperPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system_to_stage(StartupStage::PostStartup, spawn_pop_bumpers)
+            .add_system(handle_pop_bumper_skirts)
+            .add_system(animate_pop_bumper_caps);
+    }
+}
+
+//Impulse given to a ball, straight out from the bumper, when it touches the skirt.
+pub const POP_BUMPER_KICK_IMPULSE: f32 = 0.00001;
+//A bumper does not fire again until this many seconds after it fired.
+const POP_BUMPER_COOLDOWN: f64 = 0.15;
+//The cap is pulled down this far when the bumper fires, and springs back up over the animation time.
+const CAP_TRAVEL: f32 = 0.012;
+const CAP_ANIMATION_SECONDS: f64 = 0.12;
+
+const POP_BUMPER_RADIUS: f32 = 0.03;
+const POP_BUMPER_HALF_HEIGHT: f32 = 0.03;
+//The skirt sticks out this far around the body, so a ball touching the body presses it.
+const SKIRT_REACH: f32 = 0.004;
+
+const BODY_COLOR: Color = Color::ANTIQUE_WHITE;
+const SKIRT_COLOR: Color = Color::SILVER;
+const CAP_COLOR: Color = Color::PURPLE;
+const CAP_LIT_COLOR: Color = Color::FUCHSIA;
+
+#[derive(Component)]
+pub struct PopBumper {
+    // Game time, in seconds, of the last kick.
+    last_fired: f64,
+}
+
+// Sensor ring around a pop bumper.
+#[derive(Component)]
+struct PopBumperSkirt {
+    bumper: Entity,
+}
+
+#[derive(Component)]
+struct PopBumperCap {
+    rest_height: f32,
+}
+
+pub fn spawn_pop_bumpers(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    query_floors: Query<(Entity, &HalfHeight), With<Floor>>,
+) {
+    let pop_bumpers_pos: [Vec2; 3] = [
+        Vec2::new(0.22, 0.1),
+        Vec2::new(0.22, -0.08),
+        Vec2::new(-0.23, -0.28),
+    ];
+
+    let mut floor = None;
+    let mut floor_half_height = 0.0;
+    for (entity, half_height) in query_floors.iter() {
+        floor = Some(entity);
+        floor_half_height = half_height.0;
+    }
+
+    let body_mesh_handle = meshes.add(cylinder_mesh(POP_BUMPER_RADIUS, POP_BUMPER_HALF_HEIGHT, 32));
+    let skirt_mesh_handle = meshes.add(Mesh::from(shape::Torus {
+        radius: POP_BUMPER_RADIUS + SKIRT_REACH,
+        ring_radius: 0.003,
+        ..default()
+    }));
+    let cap_mesh_handle = meshes.add(Mesh::from(shape::Circle {
+        radius: POP_BUMPER_RADIUS + 0.002,
+        vertices: 32,
+    }));
+    let material_body = materials.add(BODY_COLOR.into());
+    let material_skirt = materials.add(SKIRT_COLOR.into());
+    //The cap rests above the flat top of the body, and is pressed down onto it when the bumper fires.
+    let cap_rest_height = POP_BUMPER_HALF_HEIGHT + CAP_TRAVEL + 0.001;
+
+    for pop_bumper_pos in pop_bumpers_pos {
+        //Cylinder axis is local y, turned to point out of the floor.
+        let pop_bumper = commands
+            .spawn(PbrBundle {
+                mesh: body_mesh_handle.clone(),
+                material: material_body.clone(),
+                ..default()
+            })
+            .insert(RigidBody::Fixed)
+            .insert(Collider::cylinder(
+                POP_BUMPER_HALF_HEIGHT,
+                POP_BUMPER_RADIUS,
+            ))
+            .insert(CollisionGroups {
+                memberships: Group::GROUP_2,
+                filters: Group::GROUP_3,
+            })
+            .insert(Restitution::coefficient(0.5))
+            .insert(TransformBundle::from(Transform {
+                translation: Vec3::new(
+                    pop_bumper_pos.x,
+                    pop_bumper_pos.y,
+                    floor_half_height + POP_BUMPER_HALF_HEIGHT,
+                ),
+                rotation: Quat::from_rotation_x(std::f32::consts::PI / 2.0),
+                ..default()
+            }))
+            .insert(PopBumper {
+                last_fired: f64::NEG_INFINITY,
+            })
+            .id();
+
+        //Each cap has its own material, so it can light up on its own.
+        let material_cap = materials.add(CAP_COLOR.into());
+        commands.entity(pop_bumper).with_children(|children| {
+            children
+                .spawn(Collider::cylinder(
+                    POP_BUMPER_HALF_HEIGHT,
+                    POP_BUMPER_RADIUS + SKIRT_REACH,
+                ))
+                .insert(Sensor)
+                .insert(CollisionGroups {
+                    memberships: Group::GROUP_2,
+                    filters: Group::GROUP_3,
+                })
+                .insert(TransformBundle::default())
+                .insert(PopBumperSkirt { bumper: pop_bumper });
+            children.spawn(PbrBundle {
+                mesh: skirt_mesh_handle.clone(),
+                material: material_skirt.clone(),
+                transform: Transform::from_xyz(0.0, -POP_BUMPER_HALF_HEIGHT + 0.004, 0.0),
+                ..default()
+            });
+            children
+                .spawn(PbrBundle {
+                    mesh: cap_mesh_handle.clone(),
+                    material: material_cap,
+                    transform: Transform {
+                        translation: Vec3::new(0.0, cap_rest_height, 0.0),
+                        rotation: Quat::from_rotation_x(-std::f32::consts::PI / 2.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(PopBumperCap {
+                    rest_height: cap_rest_height,
+                });
+        });
+
+        commands.entity(floor.unwrap()).add_child(pop_bumper);
+    }
+}
+
+//Mesh of a closed cylinder along the y axis, the same shape as Collider::cylinder.
+fn cylinder_mesh(radius: f32, half_height: f32, segments: u32) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    //Side, one quad per segment. The first column is repeated at the end to close the seam.
+    for segment in 0..=segments {
+        let angle = segment as f32 / segments as f32 * std::f32::consts::TAU;
+        let normal = Vec3::new(angle.cos(), 0.0, angle.sin());
+        for y in [-half_height, half_height] {
+            positions.push((normal * radius + Vec3::Y * y).into());
+            normals.push(normal.into());
+        }
+    }
+    for segment in 0..segments {
+        let a = segment * 2;
+        indices.extend([a, a + 1, a + 3, a, a + 3, a + 2]);
+    }
+
+    //Top and bottom, fans around a centre point.
+    for (y, normal) in [(half_height, Vec3::Y), (-half_height, Vec3::NEG_Y)] {
+        let center = positions.len() as u32;
+        positions.push((Vec3::Y * y).into());
+        normals.push(normal.into());
+        for segment in 0..segments {
+            let angle = segment as f32 / segments as f32 * std::f32::consts::TAU;
+            positions.push([radius * angle.cos(), y, radius * angle.sin()]);
+            normals.push(normal.into());
+        }
+        for segment in 0..segments {
+            let a = center + 1 + segment;
+            let b = center + 1 + (segment + 1) % segments;
+            if y > 0.0 {
+                indices.extend([center, b, a]);
+            } else {
+                indices.extend([center, a, b]);
+            }
+        }
+    }
+
+    let uvs = vec![[0.0, 0.0]; positions.len()];
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+//Kick the ball straight away from the bumper axis, along the contact normal.
+fn handle_pop_bumper_skirts(
+    time: Res<Time>,
+    mut contact_events: EventReader<CollisionEvent>,
+    query_skirts: Query<&PopBumperSkirt>,
+    mut query_pop_bumpers: Query<(&mut PopBumper, &GlobalTransform)>,
+    mut query_balls: Query<(&Transform, &mut ExternalImpulse), With<Ball>>,
+    mut element_hits: EventWriter<common::ElementHit>,
+) {
+    for contact_event in contact_events.iter() {
+        if let CollisionEvent::Started(h1, h2, _event_flag) = contact_event {
+            for (entity_skirt, entity_ball) in [(h1, h2), (h2, h1)] {
+                let skirt = match query_skirts.get(*entity_skirt) {
+                    Ok(skirt) => skirt,
+                    Err(_) => continue,
+                };
+                let (ball_transform, mut external_impulse) = match query_balls.get_mut(*entity_ball)
+                {
+                    Ok(ball) => ball,
+                    Err(_) => continue,
+                };
+                let (mut pop_bumper, pop_bumper_transform) =
+                    match query_pop_bumpers.get_mut(skirt.bumper) {
+                        Ok(pop_bumper) => pop_bumper,
+                        Err(_) => continue,
+                    };
+
+                let now = time.elapsed_seconds_f64();
+                if now - pop_bumper.last_fired < POP_BUMPER_COOLDOWN {
+                    continue;
+                }
+
+                let (_scale, rotation, center) =
+                    pop_bumper_transform.to_scale_rotation_translation();
+                let axis = rotation * Vec3::Y;
+                let offset = ball_transform.translation - center;
+                let radial = offset - axis * offset.dot(axis);
+                if radial.length_squared() == 0.0 {
+                    continue;
+                }
+
+                pop_bumper.last_fired = now;
+                let impulse = radial.normalize() * POP_BUMPER_KICK_IMPULSE;
+                external_impulse.impulse += impulse;
+                element_hits.send(common::ElementHit {
+                    kind: common::ElementKind::PopBumper,
+                    element: skirt.bumper,
+                    ball: *entity_ball,
+                    impulse,
+                });
+            }
+        }
+    }
+}
+
+//The cap is pulled down and lit when the bumper fires, then springs back up.
+fn animate_pop_bumper_caps(
+    time: Res<Time>,
+    query_pop_bumpers: Query<&PopBumper>,
+    mut query_caps: Query<(
+        &PopBumperCap,
+        &Parent,
+        &mut Transform,
+        &Handle<StandardMaterial>,
+    )>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (cap, parent, mut cap_transform, material) in query_caps.iter_mut() {
+        let pop_bumper = match query_pop_bumpers.get(parent.get()) {
+            Ok(pop_bumper) => pop_bumper,
+            Err(_) => continue,
+        };
+        let progress = (time.elapsed_seconds_f64() - pop_bumper.last_fired) / CAP_ANIMATION_SECONDS;
+        let pressed = (1.0 - progress).clamp(0.0, 1.0) as f32;
+
+        let height = cap.rest_height - CAP_TRAVEL * pressed;
+        if cap_transform.translation.y != height {
+            cap_transform.translation.y = height;
+            if let Some(material) = materials.get_mut(material) {
+                material.base_color = if pressed > 0.0 {
+                    CAP_LIT_COLOR
+                } else {
+                    CAP_COLOR
+                };
+            }
+        }
+    }
+}
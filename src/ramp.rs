@@ -0,0 +1,534 @@
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy_rapier3d::prelude::*;
+
+use super::common;
+use super::is_ball_in_star_collector;
+use super::Ball;
+use super::Floor;
+use super::HalfHeight;
+
+pub struct RampPlugin;
+
+impl Plugin for RampPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system_to_stage(StartupStage::PostStartup, spawn_ramps)
+            .add_system(handle_ramp_sensor_events)
+            .add_system(forget_ramp_balls.after(handle_ramp_sensor_events));
+    }
+}
+
+//Points on the ramp surface between two control points of the centerline.
+const RAMP_SAMPLES_PER_SEGMENT: usize = 8;
+const RAMP_SENSOR_HALF_HEIGHT: f32 = 0.02;
+//Steepest the centerline may climb, as the height gained along one unit of its length. Steeper
+//than this a ball can not go up, and straight up there is no level direction across the ramp.
+const RAMP_MAX_STEEPNESS: f32 = 0.9;
+//The skirts under the ramp reach this far below the lowest point of the centerline, into the floor.
+const RAMP_SKIRT_DEPTH: f32 = 0.005;
+
+// A ramp laid along a spline. The centerline runs through the control points, from the entry to the exit.
+#[derive(Clone, Debug)]
+pub struct RampSpec {
+    // Control points of the centerline in floor coordinates, on the ramp surface.
+    pub centerline: Vec<Vec3>,
+    pub width: f32,
+    // Tilt of the surface about the centerline, in radians. Positive raises the right side.
+    pub bank_angle: f32,
+    // Height of the side rails, None for no rails.
+    pub rail_height: Option<f32>,
+    pub color: Color,
+}
+
+impl RampSpec {
+    //A ramp needs a centerline of at least two distinct points to lay its surface along, and
+    //must not be too steep to have a level direction across it.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.centerline.len() < 2 {
+            return Err(format!(
+                "centerline has {} points, at least 2 are needed",
+                self.centerline.len()
+            ));
+        }
+        if self
+            .centerline
+            .windows(2)
+            .any(|pair| pair[0].distance_squared(pair[1]) <= 0.0)
+        {
+            return Err("centerline has two equal points in a row".to_string());
+        }
+        if self.width <= 0.0 {
+            return Err(format!("width {} is not positive", self.width));
+        }
+        if ramp_frames(self)
+            .iter()
+            .any(|frame| !frame.tangent.is_finite() || frame.tangent.z.abs() > RAMP_MAX_STEEPNESS)
+        {
+            return Err("centerline is too steep".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Component)]
+pub struct Ramp {
+    // Balls that went through the entry and have not come out of the exit yet.
+    balls_on_ramp: Vec<Entity>,
+    // Balls that went all the way from the entry to the exit.
+    pub completed_shots: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RampEnd {
+    Entry,
+    Exit,
+}
+
+#[derive(Component)]
+struct RampSensor {
+    ramp: Entity,
+    end: RampEnd,
+}
+
+//The ramps of the table. Add an entry to get another ramp.
+fn table_ramps(floor_half_height: f32) -> Vec<RampSpec> {
+    vec![
+        //Starramp, from the left of the playfield up into the star.
+        RampSpec {
+            centerline: vec![
+                Vec3::new(-0.17, 0.065, floor_half_height - 0.002),
+                Vec3::new(-0.115, 0.12, 0.045),
+                Vec3::new(-0.062, 0.173, 0.086),
+            ],
+            width: 0.1,
+            bank_angle: 0.0,
+            rail_height: None,
+            color: Color::rgba(1.0, 1.0, 0.0, 0.8),
+        },
+    ]
+}
+
+pub fn spawn_ramps(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    query_floors: Query<(Entity, &HalfHeight), With<Floor>>,
+) {
+    let mut floor = None;
+    let mut floor_half_height = 0.0;
+    for (entity, half_height) in query_floors.iter() {
+        floor = Some(entity);
+        floor_half_height = half_height.0;
+    }
+
+    for ramp_spec in table_ramps(floor_half_height) {
+        match spawn_ramp(&mut commands, &mut meshes, &mut materials, &ramp_spec) {
+            Ok(ramp) => {
+                commands.entity(floor.unwrap()).add_child(ramp);
+            }
+            Err(error) => warn!("Ramp left out: {}", error),
+        }
+    }
+}
+
+//Spawns a ramp with its surface, rails and sensors, in floor coordinates. Returns the ramp entity,
+//or why the spec does not make a ramp.
+pub fn spawn_ramp(
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    materials: &mut ResMut<Assets<StandardMaterial>>,
+    ramp_spec: &RampSpec,
+) -> Result<Entity, String> {
+    ramp_spec.validate()?;
+    let frames = ramp_frames(ramp_spec);
+    let (positions, normals, indices) = ramp_geometry(ramp_spec, &frames);
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        positions
+            .iter()
+            .map(|position| position.to_array())
+            .collect::<Vec<[f32; 3]>>(),
+    );
+    mesh.insert_attribute(
+        Mesh::ATTRIBUTE_NORMAL,
+        normals
+            .iter()
+            .map(|normal| normal.to_array())
+            .collect::<Vec<[f32; 3]>>(),
+    );
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; positions.len()]);
+    mesh.set_indices(Some(Indices::U32(
+        indices.iter().flatten().copied().collect(),
+    )));
+
+    //The surface and rails are thin sheets, seen from both sides.
+    let material_ramp = materials.add(StandardMaterial {
+        base_color: ramp_spec.color,
+        alpha_mode: if ramp_spec.color.a() < 1.0 {
+            AlphaMode::Blend
+        } else {
+            AlphaMode::Opaque
+        },
+        double_sided: true,
+        cull_mode: None,
+        ..default()
+    });
+
+    let ramp = commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(mesh),
+            material: material_ramp,
+            ..default()
+        })
+        .insert(RigidBody::Fixed)
+        .insert(Collider::trimesh(positions, indices))
+        .insert(CollisionGroups {
+            memberships: Group::GROUP_1,
+            filters: Group::GROUP_3,
+        })
+        .insert(Ramp {
+            balls_on_ramp: Vec::new(),
+            completed_shots: 0,
+        })
+        .id();
+
+    //The sensors stand across the ramp at each end, facing along the centerline.
+    let ends = [
+        (RampEnd::Entry, frames.first().unwrap()),
+        (RampEnd::Exit, frames.last().unwrap()),
+    ];
+    for (end, frame) in ends {
+        let ramp_sensor = commands
+            .spawn(Collider::cuboid(
+                ramp_spec.width / 2.0,
+                0.003,
+                RAMP_SENSOR_HALF_HEIGHT,
+            ))
+            .insert(Sensor)
+            .insert(TransformBundle::from(Transform {
+                translation: frame.center + frame.up * RAMP_SENSOR_HALF_HEIGHT,
+                rotation: Quat::from_mat3(&Mat3::from_cols(-frame.side, frame.tangent, frame.up)),
+                ..default()
+            }))
+            .insert(RampSensor { ramp, end })
+            .id();
+        commands.entity(ramp).add_child(ramp_sensor);
+    }
+
+    Ok(ramp)
+}
+
+// Point on the centerline with the directions of the ramp there.
+struct RampFrame {
+    center: Vec3,
+    tangent: Vec3,
+    // Across the surface, to the left when going up the ramp.
+    side: Vec3,
+    // Out of the surface.
+    up: Vec3,
+}
+
+//Samples the centerline as a Catmull-Rom spline through the control points. The centerline must
+//have at least two distinct points, and must not run straight up for the sides to be finite.
+fn ramp_frames(ramp_spec: &RampSpec) -> Vec<RampFrame> {
+    let points = &ramp_spec.centerline;
+    let segments = points.len() - 1;
+    let mut frames = Vec::new();
+    for segment in 0..segments {
+        let p0 = points[segment.saturating_sub(1)];
+        let p1 = points[segment];
+        let p2 = points[segment + 1];
+        let p3 = points[(segment + 2).min(points.len() - 1)];
+
+        let samples = if segment == segments - 1 {
+            RAMP_SAMPLES_PER_SEGMENT + 1
+        } else {
+            RAMP_SAMPLES_PER_SEGMENT
+        };
+        for sample in 0..samples {
+            let t = sample as f32 / RAMP_SAMPLES_PER_SEGMENT as f32;
+            let t2 = t * t;
+            let t3 = t2 * t;
+            let center = 0.5
+                * (2.0 * p1
+                    + (p2 - p0) * t
+                    + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+                    + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3);
+            let tangent = (0.5
+                * ((p2 - p0)
+                    + 2.0 * (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t
+                    + 3.0 * (3.0 * p1 - p0 - 3.0 * p2 + p3) * t2))
+                .normalize();
+
+            //Level across the ramp, then tilted by the bank angle.
+            let level_side = Vec3::Z.cross(tangent).normalize();
+            let side = Quat::from_axis_angle(tangent, -ramp_spec.bank_angle) * level_side;
+            let up = tangent.cross(side);
+            frames.push(RampFrame {
+                center,
+                tangent,
+                side,
+                up,
+            });
+        }
+    }
+    frames
+}
+
+//Vertices, normals and triangles of the surface, of the rails if there are any, and of the skirts
+//that close the space under the ramp.
+fn ramp_geometry(
+    ramp_spec: &RampSpec,
+    frames: &[RampFrame],
+) -> (Vec<Vec3>, Vec<Vec3>, Vec<[u32; 3]>) {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut indices = Vec::new();
+    let half_width = ramp_spec.width / 2.0;
+
+    //Each strip is two rows of points along the ramp, joined into quads.
+    let mut add_strip = |row_a: Vec<Vec3>, row_b: Vec<Vec3>, strip_normals: Vec<Vec3>| {
+        let first = positions.len() as u32;
+        for i in 0..row_a.len() {
+            positions.push(row_a[i]);
+            positions.push(row_b[i]);
+            normals.push(strip_normals[i]);
+            normals.push(strip_normals[i]);
+        }
+        for i in 0..row_a.len() as u32 - 1 {
+            let a = first + 2 * i;
+            indices.push([a, a + 1, a + 3]);
+            indices.push([a, a + 3, a + 2]);
+        }
+    };
+
+    let left: Vec<Vec3> = frames
+        .iter()
+        .map(|frame| frame.center + frame.side * half_width)
+        .collect();
+    let right: Vec<Vec3> = frames
+        .iter()
+        .map(|frame| frame.center - frame.side * half_width)
+        .collect();
+    add_strip(
+        left.clone(),
+        right.clone(),
+        frames.iter().map(|frame| frame.up).collect(),
+    );
+
+    if let Some(rail_height) = ramp_spec.rail_height {
+        for (edge, inwards) in [(&left, -1.0), (&right, 1.0)] {
+            let top = edge
+                .iter()
+                .zip(frames)
+                .map(|(point, frame)| *point + frame.up * rail_height)
+                .collect();
+            add_strip(
+                edge.clone(),
+                top,
+                frames.iter().map(|frame| frame.side * inwards).collect(),
+            );
+        }
+    }
+
+    //Skirts down from both edges and across the exit, so balls can not roll in under the ramp.
+    let bottom = ramp_spec
+        .centerline
+        .iter()
+        .map(|point| point.z)
+        .fold(f32::INFINITY, f32::min)
+        - RAMP_SKIRT_DEPTH;
+    let foot = |point: &Vec3| Vec3::new(point.x, point.y, bottom);
+    for (edge, outwards) in [(&left, 1.0), (&right, -1.0)] {
+        add_strip(
+            edge.clone(),
+            edge.iter().map(foot).collect(),
+            frames.iter().map(|frame| frame.side * outwards).collect(),
+        );
+    }
+    let exit = [*left.last().unwrap(), *right.last().unwrap()];
+    add_strip(
+        exit.to_vec(),
+        exit.iter().map(foot).collect(),
+        vec![frames.last().unwrap().tangent; 2],
+    );
+
+    (positions, normals, indices)
+}
+
+//A ball that goes through the entry and then the exit of a ramp makes a ramp shot.
+fn handle_ramp_sensor_events(
+    mut contact_events: EventReader<CollisionEvent>,
+    query_sensors: Query<&RampSensor>,
+    query_balls: Query<Entity, With<Ball>>,
+    mut query_ramps: Query<&mut Ramp>,
+    mut ramp_shots: EventWriter<common::RampShot>,
+) {
+    for contact_event in contact_events.iter() {
+        if let CollisionEvent::Started(h1, h2, _event_flag) = contact_event {
+            for (entity_sensor, entity_ball) in [(h1, h2), (h2, h1)] {
+                let sensor = match query_sensors.get(*entity_sensor) {
+                    Ok(sensor) => sensor,
+                    Err(_) => continue,
+                };
+                if !query_balls.contains(*entity_ball) {
+                    continue;
+                }
+                let mut ramp = match query_ramps.get_mut(sensor.ramp) {
+                    Ok(ramp) => ramp,
+                    Err(_) => continue,
+                };
+
+                match sensor.end {
+                    RampEnd::Entry => {
+                        if !ramp.balls_on_ramp.contains(entity_ball) {
+                            ramp.balls_on_ramp.push(*entity_ball);
+                        }
+                    }
+                    RampEnd::Exit => {
+                        let before = ramp.balls_on_ramp.len();
+                        ramp.balls_on_ramp.retain(|ball| ball != entity_ball);
+                        if ramp.balls_on_ramp.len() < before {
+                            ramp.completed_shots += 1;
+                            ramp_shots.send(common::RampShot {
+                                ramp: sensor.ramp,
+                                ball: *entity_ball,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+//A ball that drains or is caught by the star collector while on a ramp is not coming out of the exit.
+fn forget_ramp_balls(
+    mut ball_drained: EventReader<common::BallDrained>,
+    query_balls: Query<&CollisionGroups, With<Ball>>,
+    mut query_ramps: Query<&mut Ramp>,
+) {
+    let drained: Vec<Entity> = ball_drained.iter().map(|drained| drained.ball).collect();
+    for mut ramp in query_ramps.iter_mut() {
+        if ramp.balls_on_ramp.is_empty() {
+            continue;
+        }
+        ramp.balls_on_ramp.retain(|ball| {
+            !drained.contains(ball)
+                && match query_balls.get(*ball) {
+                    Ok(collision_groups) => !is_ball_in_star_collector(collision_groups),
+                    Err(_) => false,
+                }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ramp_spec(rail_height: Option<f32>) -> RampSpec {
+        RampSpec {
+            centerline: vec![
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(0.0, 0.1, 0.02),
+                Vec3::new(0.05, 0.2, 0.05),
+            ],
+            width: 0.1,
+            bank_angle: 0.1,
+            rail_height,
+            color: Color::WHITE,
+        }
+    }
+
+    #[test]
+    fn frames_run_from_entry_to_exit() {
+        let spec = ramp_spec(None);
+        let frames = ramp_frames(&spec);
+        assert_eq!(frames.len(), 2 * RAMP_SAMPLES_PER_SEGMENT + 1);
+        assert!(frames[0].center.distance(spec.centerline[0]) < 1e-6);
+        assert!(
+            frames[RAMP_SAMPLES_PER_SEGMENT]
+                .center
+                .distance(spec.centerline[1])
+                < 1e-6
+        );
+        assert!(frames.last().unwrap().center.distance(spec.centerline[2]) < 1e-6);
+    }
+
+    #[test]
+    fn frames_are_orthonormal_and_face_up() {
+        let spec = ramp_spec(None);
+        for frame in ramp_frames(&spec) {
+            for direction in [frame.tangent, frame.side, frame.up] {
+                assert!((direction.length() - 1.0).abs() < 1e-5);
+            }
+            assert!(frame.tangent.dot(frame.side).abs() < 1e-5);
+            assert!(frame.tangent.dot(frame.up).abs() < 1e-5);
+            assert!(frame.side.dot(frame.up).abs() < 1e-5);
+            assert!(frame.up.z > 0.0);
+            //A positive bank angle raises the right side, so the left side points down.
+            assert!(frame.side.z < 0.0);
+        }
+    }
+
+    #[test]
+    fn geometry_has_surface_rails_and_skirts() {
+        for (rail_height, strips) in [(None, 3), (Some(0.02), 5)] {
+            let spec = ramp_spec(rail_height);
+            let frames = ramp_frames(&spec);
+            let (positions, normals, indices) = ramp_geometry(&spec, &frames);
+
+            //Strips along the ramp, plus the quad across the exit.
+            assert_eq!(positions.len(), strips * 2 * frames.len() + 4);
+            assert_eq!(normals.len(), positions.len());
+            assert_eq!(indices.len(), strips * 2 * (frames.len() - 1) + 2);
+            assert!(indices
+                .iter()
+                .flatten()
+                .all(|index| (*index as usize) < positions.len()));
+
+            //The skirts reach into the floor below the entry.
+            let lowest = positions
+                .iter()
+                .map(|position| position.z)
+                .fold(f32::INFINITY, f32::min);
+            assert!((lowest - (spec.centerline[0].z - RAMP_SKIRT_DEPTH)).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn validate_rejects_ramps_without_a_centerline() {
+        assert!(ramp_spec(None).validate().is_ok());
+
+        let mut spec = ramp_spec(None);
+        spec.centerline.truncate(1);
+        assert!(spec.validate().is_err());
+
+        let mut spec = ramp_spec(None);
+        spec.centerline[1] = spec.centerline[0];
+        assert!(spec.validate().is_err());
+
+        let mut spec = ramp_spec(None);
+        spec.width = 0.0;
+        assert!(spec.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_vertical_centerline() {
+        let mut spec = ramp_spec(None);
+        spec.centerline = vec![Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.1)];
+        assert!(spec.validate().is_err());
+
+        //Not straight up, but still too steep to roll up.
+        let mut spec = ramp_spec(None);
+        spec.centerline = vec![
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.01, 0.1),
+            Vec3::new(0.0, 0.02, 0.2),
+        ];
+        assert!(spec.validate().is_err());
+    }
+}
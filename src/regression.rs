@@ -0,0 +1,251 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::utils::Instant;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::heatmap::{simulate_heatmap, HeatmapOptions};
+use super::is_ball_on_launcher;
+use super::Ball;
+use super::LAUNCHER_LANE_MIN_X;
+use super::{FlipperPolicy, Simulation, SIMULATION_DT};
+use super::{InputAction, VirtualActions};
+
+//Ball counts the throughput is measured with.
+pub const BENCHMARK_BALL_COUNTS: [usize; 3] = [1, 5, 20];
+const THROUGHPUT_TICKS: u64 = 600;
+
+//Launches are tried with the launcher held for LAUNCH_TRIAL_TICKS, twice as long, and so on
+//up to LAUNCH_TRIALS times as long. That goes from a light tap to a full pull of the plunger.
+const LAUNCH_TRIALS: u32 = 20;
+const LAUNCH_TRIAL_TICKS: u32 = 3;
+//A launched ball must have left the launcher lane within this time.
+const LAUNCH_TIMEOUT_SECONDS: f32 = 3.0;
+//Ticks to wait for the first ball to come to rest on the launcher.
+const LAUNCH_SETTLE_TICKS: u64 = 300;
+
+const REACHABILITY_BALLS: u32 = 20;
+
+//Gameplay metrics are deterministic, any change larger than this is reported.
+const GAMEPLAY_TOLERANCE: f32 = 0.05;
+//Throughput depends on the machine, so only large drops are reported.
+const THROUGHPUT_TOLERANCE: f32 = 0.2;
+
+#[derive(Serialize, Deserialize)]
+pub struct Throughput {
+    pub balls: usize,
+    pub ticks_per_second: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RegressionReport {
+    pub throughput: Vec<Throughput>,
+    // Share of launches, over a range of plunger pulls, that got the ball out of the launcher lane.
+    pub launch_success_rate: f32,
+    // Share of balls the autopilot got into the star collector.
+    pub collector_reachability: f32,
+}
+
+// A table with `balls` balls in play: the one on the launcher plus extra balls dropped on the upper playfield.
+pub fn simulation_with_balls(balls: usize) -> Simulation {
+    let mut simulation = Simulation::new(SIMULATION_DT, FlipperPolicy::Random, 0);
+    simulation.step();
+
+    let extra_positions: Vec<Vec2> = (1..balls)
+        .map(|i| {
+            let column = (i % 5) as f32;
+            let row = (i / 5) as f32;
+            Vec2::new(-0.2 + column * 0.1, 0.0 - row * 0.08)
+        })
+        .collect();
+    simulation.spawn_balls_on_floor(&extra_positions);
+    simulation
+}
+
+pub fn measure_ticks_per_second(balls: usize, ticks: u64) -> f32 {
+    let mut simulation = simulation_with_balls(balls);
+    let start = Instant::now();
+    for _ in 0..ticks {
+        simulation.step();
+    }
+    ticks as f32 / start.elapsed().as_secs_f32()
+}
+
+//Hold the launcher for hold_ticks once the ball rests on it. True if the ball left the launcher lane.
+pub fn try_launch(hold_ticks: u32) -> bool {
+    let mut simulation = Simulation::new(SIMULATION_DT, FlipperPolicy::External, 0);
+
+    let mut settled = false;
+    while !settled && simulation.tick() < LAUNCH_SETTLE_TICKS {
+        simulation.step();
+        let world = &mut simulation.app.world;
+        settled = world
+            .query_filtered::<(&Transform, &Velocity), With<Ball>>()
+            .iter(world)
+            .any(|(ball_transform, ball_velocity)| {
+                is_ball_on_launcher(ball_transform, ball_velocity)
+            });
+    }
+    if !settled {
+        return false;
+    }
+
+    let launch_tick = simulation.tick();
+    let timeout_ticks = (LAUNCH_TIMEOUT_SECONDS / SIMULATION_DT) as u64;
+    while simulation.tick() - launch_tick < timeout_ticks {
+        let pressed = simulation.tick() - launch_tick < hold_ticks as u64;
+        let world = &mut simulation.app.world;
+        world
+            .resource_mut::<VirtualActions>()
+            .set(InputAction::Launch, pressed);
+        simulation.step();
+
+        let world = &mut simulation.app.world;
+        let left_lane = world
+            .query_filtered::<&Transform, With<Ball>>()
+            .iter(world)
+            .any(|ball_transform| ball_transform.translation.x < LAUNCHER_LANE_MIN_X - 0.02);
+        if left_lane {
+            return true;
+        }
+    }
+    false
+}
+
+pub fn launch_success_rate() -> f32 {
+    let successes = (1..=LAUNCH_TRIALS)
+        .filter(|trial| try_launch(trial * LAUNCH_TRIAL_TICKS))
+        .count();
+    successes as f32 / LAUNCH_TRIALS as f32
+}
+
+pub fn collector_reachability(balls: u32) -> f32 {
+    let options = HeatmapOptions {
+        balls,
+        policy: FlipperPolicy::Autopilot,
+        ..default()
+    };
+    let (summary, _image) = simulate_heatmap(&options);
+    if summary.balls == 0 {
+        0.0
+    } else {
+        summary.collected as f32 / summary.balls as f32
+    }
+}
+
+pub fn regression_report() -> RegressionReport {
+    RegressionReport {
+        throughput: BENCHMARK_BALL_COUNTS
+            .iter()
+            .map(|balls| Throughput {
+                balls: *balls,
+                ticks_per_second: measure_ticks_per_second(*balls, THROUGHPUT_TICKS),
+            })
+            .collect(),
+        launch_success_rate: launch_success_rate(),
+        collector_reachability: collector_reachability(REACHABILITY_BALLS),
+    }
+}
+
+// Differences to a baseline report that are larger than the tolerances.
+pub fn compare_reports(baseline: &RegressionReport, report: &RegressionReport) -> Vec<String> {
+    let mut regressions = Vec::new();
+
+    for throughput in report.throughput.iter() {
+        let baseline_throughput = baseline
+            .throughput
+            .iter()
+            .find(|baseline_throughput| baseline_throughput.balls == throughput.balls);
+        if let Some(baseline_throughput) = baseline_throughput {
+            if throughput.ticks_per_second
+                < baseline_throughput.ticks_per_second * (1.0 - THROUGHPUT_TOLERANCE)
+            {
+                regressions.push(format!(
+                    "Throughput with {} balls dropped from {:.0} to {:.0} ticks per second",
+                    throughput.balls,
+                    baseline_throughput.ticks_per_second,
+                    throughput.ticks_per_second
+                ));
+            }
+        }
+    }
+
+    for (name, baseline_value, value) in [
+        (
+            "Launch success rate",
+            baseline.launch_success_rate,
+            report.launch_success_rate,
+        ),
+        (
+            "Collector reachability",
+            baseline.collector_reachability,
+            report.collector_reachability,
+        ),
+    ] {
+        if (value - baseline_value).abs() > GAMEPLAY_TOLERANCE {
+            regressions.push(format!(
+                "{} changed from {:.2} to {:.2}",
+                name, baseline_value, value
+            ));
+        }
+    }
+
+    regressions
+}
+
+pub fn run_regression_report_command(args: &[String]) {
+    let mut out = PathBuf::from("regression_report.json");
+    let mut baseline = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--out", Some(value)) => out = PathBuf::from(value),
+            ("--baseline", Some(value)) => baseline = Some(PathBuf::from(value)),
+            _ => {
+                eprintln!("Usage: pinball3d regression-report [--out FILE] [--baseline FILE]");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let report = regression_report();
+    for throughput in report.throughput.iter() {
+        println!(
+            "{:>2} balls: {:.0} ticks per second",
+            throughput.balls, throughput.ticks_per_second
+        );
+    }
+    println!("Launch success rate: {:.2}", report.launch_success_rate);
+    println!(
+        "Collector reachability: {:.2}",
+        report.collector_reachability
+    );
+
+    let json = serde_json::to_string_pretty(&report).expect("Report is always serializable");
+    if let Err(error) = std::fs::write(&out, json) {
+        eprintln!("Could not write {}: {}", out.display(), error);
+        std::process::exit(1);
+    }
+
+    if let Some(baseline) = baseline {
+        let baseline_report: RegressionReport = match std::fs::read_to_string(&baseline)
+            .map_err(|error| error.to_string())
+            .and_then(|json| serde_json::from_str(&json).map_err(|error| error.to_string()))
+        {
+            Ok(baseline_report) => baseline_report,
+            Err(error) => {
+                eprintln!("Could not read baseline {}: {}", baseline.display(), error);
+                std::process::exit(1);
+            }
+        };
+
+        let regressions = compare_reports(&baseline_report, &report);
+        for regression in regressions.iter() {
+            println!("REGRESSION: {}", regression);
+        }
+        if !regressions.is_empty() {
+            std::process::exit(1);
+        }
+    }
+}
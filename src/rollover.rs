@@ -0,0 +1,299 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::common;
+use super::Ball;
+use super::Floor;
+use super::HalfHeight;
+use super::Wall;
+use super::{ActionState, InputAction};
+
+pub struct RolloverPlugin;
+
+impl Plugin for RolloverPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RolloverLanes>()
+            .add_startup_system_to_stage(StartupStage::PostStartup, spawn_rollover_lanes)
+            .add_system(handle_rollover_sensor_events)
+            .add_system(shift_rollover_lanes)
+            .add_system(
+                complete_rollover_lanes
+                    .after(handle_rollover_sensor_events)
+                    .after(shift_rollover_lanes),
+            )
+            .add_system(light_rollover_lamps.after(complete_rollover_lanes));
+    }
+}
+
+//The bonus multiplier goes up by one each time all lanes are lit, up to this.
+pub const MAX_BONUS_MULTIPLIER: u32 = 5;
+
+//Guides between the lanes, so a ball rolls through one lane at a time.
+const LANE_GUIDE_HALF_SIZE: Vec3 = Vec3::new(0.004, 0.025, 0.02);
+
+const LAMP_COLOR: Color = Color::rgb(0.2, 0.2, 0.1);
+const LAMP_LIT_COLOR: Color = Color::YELLOW;
+
+// Lit state of the rollover lanes, from left to right.
+#[derive(Resource, Default)]
+pub struct RolloverLanes {
+    pub lit: Vec<bool>,
+}
+
+impl RolloverLanes {
+    //Moves the lit lanes one lane left, the first wrapping around to the last.
+    fn shift_left(&mut self) {
+        if !self.lit.is_empty() {
+            self.lit.rotate_left(1);
+        }
+    }
+
+    //Moves the lit lanes one lane right, the last wrapping around to the first.
+    fn shift_right(&mut self) {
+        if !self.lit.is_empty() {
+            self.lit.rotate_right(1);
+        }
+    }
+
+    //Puts out every lane if all are lit. Returns whether they were.
+    fn complete(&mut self) -> bool {
+        if self.lit.is_empty() || !self.lit.iter().all(|lit| *lit) {
+            return false;
+        }
+        for lit in self.lit.iter_mut() {
+            *lit = false;
+        }
+        true
+    }
+}
+
+//Bonus multiplier after all lanes were lit.
+fn raised_bonus_multiplier(bonus_multiplier: u32) -> u32 {
+    (bonus_multiplier + 1).min(MAX_BONUS_MULTIPLIER)
+}
+
+// Thin sensor across a lane, like the gate sensor. Lit when a ball rolls over it.
+#[derive(Component)]
+struct RolloverSensor {
+    lane: usize,
+}
+
+#[derive(Component)]
+struct RolloverLamp {
+    lane: usize,
+}
+
+pub fn spawn_rollover_lanes(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut rollover_lanes: ResMut<RolloverLanes>,
+    query_floors: Query<(Entity, &HalfHeight), With<Floor>>,
+) {
+    //Top lanes right of the star, from left to right.
+    let lanes_pos: [Vec2; 3] = [
+        Vec2::new(0.14, 0.17),
+        Vec2::new(0.2, 0.17),
+        Vec2::new(0.26, 0.17),
+    ];
+
+    let mut floor = None;
+    let mut floor_half_height = 0.0;
+    for (entity, half_height) in query_floors.iter() {
+        floor = Some(entity);
+        floor_half_height = half_height.0;
+    }
+
+    let lamp_mesh_handle = meshes.add(Mesh::from(shape::Circle {
+        radius: 0.01,
+        vertices: 24,
+    }));
+
+    rollover_lanes.lit = vec![false; lanes_pos.len()];
+
+    for (lane, lane_pos) in lanes_pos.into_iter().enumerate() {
+        let rollover_sensor = commands
+            .spawn(Collider::cuboid(0.025, 0.003, 0.02))
+            .insert(Sensor)
+            .insert(TransformBundle::from(Transform::from_xyz(
+                lane_pos.x,
+                lane_pos.y,
+                floor_half_height + 0.02,
+            )))
+            .insert(RolloverSensor { lane })
+            .id();
+
+        //Each lamp has its own material, so it can light up on its own.
+        let rollover_lamp = commands
+            .spawn(PbrBundle {
+                mesh: lamp_mesh_handle.clone(),
+                material: materials.add(LAMP_COLOR.into()),
+                transform: Transform::from_xyz(
+                    lane_pos.x,
+                    lane_pos.y - 0.025,
+                    floor_half_height + 0.001,
+                ),
+                ..default()
+            })
+            .insert(RolloverLamp { lane })
+            .id();
+
+        commands
+            .entity(floor.unwrap())
+            .push_children(&[rollover_sensor, rollover_lamp]);
+    }
+
+    //The star bumper left of the first lane guides that side.
+    let guide_mesh_handle = meshes.add(Mesh::from(shape::Box::new(
+        LANE_GUIDE_HALF_SIZE.x * 2.0,
+        LANE_GUIDE_HALF_SIZE.y * 2.0,
+        LANE_GUIDE_HALF_SIZE.z * 2.0,
+    )));
+    let material_guide = materials.add(Color::CYAN.into());
+    for lane_pair in lanes_pos.windows(2) {
+        let guide_pos = (lane_pair[0] + lane_pair[1]) / 2.0;
+        let guide = commands
+            .spawn(PbrBundle {
+                mesh: guide_mesh_handle.clone(),
+                material: material_guide.clone(),
+                ..default()
+            })
+            .insert(RigidBody::Fixed)
+            .insert(Collider::cuboid(
+                LANE_GUIDE_HALF_SIZE.x,
+                LANE_GUIDE_HALF_SIZE.y,
+                LANE_GUIDE_HALF_SIZE.z,
+            ))
+            .insert(Wall)
+            .insert(CollisionGroups {
+                memberships: Group::GROUP_2,
+                filters: Group::GROUP_3,
+            })
+            .insert(TransformBundle::from(Transform::from_xyz(
+                guide_pos.x,
+                guide_pos.y,
+                floor_half_height + LANE_GUIDE_HALF_SIZE.z,
+            )))
+            .id();
+        commands.entity(floor.unwrap()).add_child(guide);
+    }
+}
+
+fn handle_rollover_sensor_events(
+    mut contact_events: EventReader<CollisionEvent>,
+    query_sensors: Query<&RolloverSensor>,
+    query_balls: Query<Entity, With<Ball>>,
+    mut rollover_lanes: ResMut<RolloverLanes>,
+    mut lane_passes: EventWriter<common::RolloverLanePassed>,
+) {
+    for contact_event in contact_events.iter() {
+        if let CollisionEvent::Started(h1, h2, _event_flag) = contact_event {
+            for (entity_sensor, entity_ball) in [(h1, h2), (h2, h1)] {
+                let sensor = match query_sensors.get(*entity_sensor) {
+                    Ok(sensor) => sensor,
+                    Err(_) => continue,
+                };
+                if !query_balls.contains(*entity_ball) {
+                    continue;
+                }
+                if let Some(lit) = rollover_lanes.lit.get_mut(sensor.lane) {
+                    *lit = true;
+                }
+                lane_passes.send(common::RolloverLanePassed {
+                    lane: sensor.lane,
+                    ball: *entity_ball,
+                });
+            }
+        }
+    }
+}
+
+//The flipper buttons move the lit lanes one lane left or right, wrapping around,
+//so the player can steer an unlit lane under the ball.
+fn shift_rollover_lanes(action_state: Res<ActionState>, mut rollover_lanes: ResMut<RolloverLanes>) {
+    if action_state.just_pressed(InputAction::LeftFlipper) {
+        rollover_lanes.shift_left();
+    }
+    if action_state.just_pressed(InputAction::RightFlipper) {
+        rollover_lanes.shift_right();
+    }
+}
+
+//Lighting every lane raises the bonus multiplier and starts the lanes over.
+fn complete_rollover_lanes(
+    mut rollover_lanes: ResMut<RolloverLanes>,
+    mut bonus_multiplier: ResMut<common::BonusMultiplier>,
+) {
+    if rollover_lanes.complete() {
+        bonus_multiplier.0 = raised_bonus_multiplier(bonus_multiplier.0);
+    }
+}
+
+fn light_rollover_lamps(
+    rollover_lanes: Res<RolloverLanes>,
+    query_lamps: Query<(&RolloverLamp, &Handle<StandardMaterial>)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !rollover_lanes.is_changed() {
+        return;
+    }
+    for (lamp, material) in query_lamps.iter() {
+        let lit = rollover_lanes.lit.get(lamp.lane).copied().unwrap_or(false);
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color = if lit { LAMP_LIT_COLOR } else { LAMP_COLOR };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lanes(lit: &[bool]) -> RolloverLanes {
+        RolloverLanes { lit: lit.to_vec() }
+    }
+
+    #[test]
+    fn shifting_wraps_around() {
+        let mut rollover_lanes = lanes(&[true, false, false]);
+        rollover_lanes.shift_left();
+        assert_eq!(rollover_lanes.lit, [false, false, true]);
+        rollover_lanes.shift_right();
+        rollover_lanes.shift_right();
+        assert_eq!(rollover_lanes.lit, [false, true, false]);
+    }
+
+    #[test]
+    fn shifting_no_lanes_does_nothing() {
+        let mut rollover_lanes = lanes(&[]);
+        rollover_lanes.shift_left();
+        rollover_lanes.shift_right();
+        assert!(rollover_lanes.lit.is_empty());
+    }
+
+    #[test]
+    fn lanes_complete_only_when_all_are_lit() {
+        let mut rollover_lanes = lanes(&[true, false, true]);
+        assert!(!rollover_lanes.complete());
+        assert_eq!(rollover_lanes.lit, [true, false, true]);
+
+        assert!(!lanes(&[]).complete());
+
+        let mut rollover_lanes = lanes(&[true, true, true]);
+        assert!(rollover_lanes.complete());
+        assert_eq!(rollover_lanes.lit, [false, false, false]);
+    }
+
+    #[test]
+    fn bonus_multiplier_is_capped() {
+        assert_eq!(raised_bonus_multiplier(1), 2);
+        assert_eq!(
+            raised_bonus_multiplier(MAX_BONUS_MULTIPLIER - 1),
+            MAX_BONUS_MULTIPLIER
+        );
+        assert_eq!(
+            raised_bonus_multiplier(MAX_BONUS_MULTIPLIER),
+            MAX_BONUS_MULTIPLIER
+        );
+    }
+}
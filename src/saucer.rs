@@ -0,0 +1,296 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::common;
+use super::Ball;
+use super::Floor;
+use super::HalfHeight;
+use super::BALL_RADIUS;
+
+pub struct SaucerPlugin;
+
+impl Plugin for SaucerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system_to_stage(StartupStage::PostStartup, spawn_saucers)
+            .add_system(handle_saucer_sensor_events)
+            .add_system(hold_and_eject_saucer_balls.after(handle_saucer_sensor_events));
+    }
+}
+
+//A saucer does not catch a ball again until this many seconds after it ejected one, so the
+//ejected ball can roll out.
+const SAUCER_RECAPTURE_DELAY: f64 = 0.5;
+//How far a held ball sinks into the saucer.
+const SAUCER_DEPTH: f32 = 0.006;
+const SAUCER_RADIUS: f32 = 0.02;
+
+// Shallow hole in the floor. It catches a ball, holds it for a while and ejects it.
+#[derive(Component)]
+pub struct Saucer {
+    pub hold_seconds: f64,
+    // Direction the ball is ejected in, in floor coordinates.
+    pub eject_direction: Vec2,
+    pub eject_impulse: f32,
+    // Ball held, and game time, in seconds, it was caught.
+    held: Option<(Entity, f64)>,
+    // Game time, in seconds, of the last eject.
+    last_ejected: f64,
+}
+
+impl Saucer {
+    pub fn new(hold_seconds: f64, eject_direction: Vec2, eject_impulse: f32) -> Self {
+        Saucer {
+            hold_seconds,
+            eject_direction: eject_direction.normalize(),
+            eject_impulse,
+            held: None,
+            last_ejected: f64::NEG_INFINITY,
+        }
+    }
+
+    //An empty saucer catches a ball, but not the one it just ejected while that rolls out.
+    fn can_catch(&self, now: f64) -> bool {
+        self.held.is_none() && now - self.last_ejected >= SAUCER_RECAPTURE_DELAY
+    }
+
+    fn catch(&mut self, ball: Entity, now: f64) {
+        self.held = Some((ball, now));
+    }
+
+    //The held ball, once it has been held for the hold time.
+    fn ball_to_eject(&self, now: f64) -> Option<Entity> {
+        match self.held {
+            Some((ball, caught_at)) if now - caught_at >= self.hold_seconds => Some(ball),
+            _ => None,
+        }
+    }
+
+    //Lets go of the held ball, and returns the impulse that kicks it out. The impulse is in
+    //world coordinates, for a floor turned by floor_rotation.
+    fn eject(&mut self, now: f64, floor_rotation: Quat) -> Vec3 {
+        self.held = None;
+        self.last_ejected = now;
+        floor_rotation * self.eject_direction.extend(0.0) * self.eject_impulse
+    }
+}
+
+#[derive(Component)]
+struct SaucerSensor {
+    saucer: Entity,
+}
+
+pub fn spawn_saucers(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    query_floors: Query<(Entity, &HalfHeight), With<Floor>>,
+) {
+    //Position, hold time, eject direction and eject impulse of each saucer.
+    let saucers: [(Vec2, f64, Vec2, f32); 1] =
+        [(Vec2::new(0.2, -0.55), 2.0, Vec2::new(-0.5, 1.0), 0.000008)];
+
+    let mut floor = None;
+    let mut floor_half_height = 0.0;
+    for (entity, half_height) in query_floors.iter() {
+        floor = Some(entity);
+        floor_half_height = half_height.0;
+    }
+
+    let hole_mesh_handle = meshes.add(Mesh::from(shape::Circle {
+        radius: SAUCER_RADIUS,
+        vertices: 32,
+    }));
+    let rim_mesh_handle = meshes.add(Mesh::from(shape::Torus {
+        radius: SAUCER_RADIUS,
+        ring_radius: 0.002,
+        ..default()
+    }));
+    let material_hole = materials.add(Color::BLACK.into());
+    let material_rim = materials.add(Color::SILVER.into());
+
+    for (saucer_pos, hold_seconds, eject_direction, eject_impulse) in saucers {
+        let saucer = commands
+            .spawn(PbrBundle {
+                mesh: hole_mesh_handle.clone(),
+                material: material_hole.clone(),
+                transform: Transform::from_xyz(
+                    saucer_pos.x,
+                    saucer_pos.y,
+                    floor_half_height + 0.001,
+                ),
+                ..default()
+            })
+            .insert(Saucer::new(hold_seconds, eject_direction, eject_impulse))
+            .id();
+
+        commands.entity(saucer).with_children(|children| {
+            //The torus lies in its local xz plane, turned to lie on the floor.
+            children.spawn(PbrBundle {
+                mesh: rim_mesh_handle.clone(),
+                material: material_rim.clone(),
+                transform: Transform::from_rotation(Quat::from_rotation_x(
+                    std::f32::consts::PI / 2.0,
+                )),
+                ..default()
+            });
+            //A ball touches the sensor when it is over the hole.
+            children
+                .spawn(Collider::ball(0.005))
+                .insert(Sensor)
+                .insert(TransformBundle::from(Transform::from_xyz(
+                    0.0,
+                    0.0,
+                    BALL_RADIUS,
+                )))
+                .insert(SaucerSensor { saucer });
+        });
+
+        commands.entity(floor.unwrap()).add_child(saucer);
+    }
+}
+
+//An empty saucer catches a ball that rolls over it. The ball is held by making it kinematic.
+fn handle_saucer_sensor_events(
+    time: Res<Time>,
+    mut contact_events: EventReader<CollisionEvent>,
+    query_sensors: Query<&SaucerSensor>,
+    mut query_saucers: Query<&mut Saucer>,
+    mut query_balls: Query<(&mut RigidBody, &mut Velocity), With<Ball>>,
+    mut saucer_holds: EventWriter<common::SaucerHold>,
+) {
+    let now = time.elapsed_seconds_f64();
+    for contact_event in contact_events.iter() {
+        if let CollisionEvent::Started(h1, h2, _event_flag) = contact_event {
+            for (entity_sensor, entity_ball) in [(h1, h2), (h2, h1)] {
+                let sensor = match query_sensors.get(*entity_sensor) {
+                    Ok(sensor) => sensor,
+                    Err(_) => continue,
+                };
+                let mut saucer = match query_saucers.get_mut(sensor.saucer) {
+                    Ok(saucer) => saucer,
+                    Err(_) => continue,
+                };
+                if !saucer.can_catch(now) {
+                    continue;
+                }
+                let (mut rigid_body, mut velocity) = match query_balls.get_mut(*entity_ball) {
+                    Ok(ball) => ball,
+                    Err(_) => continue,
+                };
+
+                *rigid_body = RigidBody::KinematicPositionBased;
+                velocity.linvel = Vec3::ZERO;
+                velocity.angvel = Vec3::ZERO;
+                saucer.catch(*entity_ball, now);
+                saucer_holds.send(common::SaucerHold {
+                    saucer: sensor.saucer,
+                    ball: *entity_ball,
+                });
+            }
+        }
+    }
+}
+
+//Keeps a held ball sunk in its saucer. When the hold time is up, the ball is lifted back onto
+//the floor, made dynamic again and ejected.
+fn hold_and_eject_saucer_balls(
+    time: Res<Time>,
+    mut query_saucers: Query<(Entity, &mut Saucer, &GlobalTransform)>,
+    mut query_balls: Query<
+        (
+            &mut RigidBody,
+            &mut Transform,
+            &mut Velocity,
+            &mut ExternalImpulse,
+        ),
+        With<Ball>,
+    >,
+    mut element_hits: EventWriter<common::ElementHit>,
+) {
+    let now = time.elapsed_seconds_f64();
+    for (entity_saucer, mut saucer, saucer_transform) in query_saucers.iter_mut() {
+        let entity_ball = match saucer.held {
+            Some((entity_ball, _caught_at)) => entity_ball,
+            None => continue,
+        };
+        let (mut rigid_body, mut ball_transform, mut velocity, mut external_impulse) =
+            match query_balls.get_mut(entity_ball) {
+                Ok(ball) => ball,
+                Err(_) => {
+                    //The ball is gone, the saucer is free again.
+                    saucer.held = None;
+                    continue;
+                }
+            };
+
+        let (_scale, floor_rotation, hole_center) =
+            saucer_transform.to_scale_rotation_translation();
+        let floor_normal = floor_rotation * Vec3::Z;
+
+        if saucer.ball_to_eject(now).is_none() {
+            ball_transform.translation = hole_center + floor_normal * (BALL_RADIUS - SAUCER_DEPTH);
+            continue;
+        }
+
+        ball_transform.translation = hole_center + floor_normal * BALL_RADIUS;
+        *rigid_body = RigidBody::Dynamic;
+        velocity.linvel = Vec3::ZERO;
+        let impulse = saucer.eject(now, floor_rotation);
+        external_impulse.impulse += impulse;
+        element_hits.send(common::ElementHit {
+            kind: common::ElementKind::Saucer,
+            element: entity_saucer,
+            ball: entity_ball,
+            impulse,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saucer() -> Saucer {
+        Saucer::new(2.0, Vec2::new(-0.5, 1.0), 0.000008)
+    }
+
+    #[test]
+    fn catches_one_ball_at_a_time() {
+        let mut saucer = saucer();
+        assert!(saucer.can_catch(0.0));
+        saucer.catch(Entity::from_raw(1), 0.0);
+        assert!(!saucer.can_catch(0.1));
+    }
+
+    #[test]
+    fn holds_the_ball_for_the_hold_time() {
+        let mut saucer = saucer();
+        let ball = Entity::from_raw(1);
+        saucer.catch(ball, 10.0);
+        assert_eq!(saucer.ball_to_eject(10.0), None);
+        assert_eq!(saucer.ball_to_eject(11.99), None);
+        assert_eq!(saucer.ball_to_eject(12.0), Some(ball));
+    }
+
+    #[test]
+    fn kicks_out_along_the_eject_direction() {
+        let mut saucer = saucer();
+        saucer.catch(Entity::from_raw(1), 0.0);
+        let floor_rotation = Quat::from_rotation_x(0.12);
+        let impulse = saucer.eject(2.0, floor_rotation);
+
+        assert!((impulse.length() - saucer.eject_impulse).abs() < 1e-9);
+        let direction = floor_rotation.inverse() * impulse.normalize();
+        assert!(direction.distance(Vec2::new(-0.5, 1.0).normalize().extend(0.0)) < 1e-5);
+        assert_eq!(saucer.ball_to_eject(2.0), None);
+    }
+
+    #[test]
+    fn does_not_catch_the_ejected_ball_while_it_rolls_out() {
+        let mut saucer = saucer();
+        saucer.catch(Entity::from_raw(1), 0.0);
+        saucer.eject(2.0, Quat::IDENTITY);
+        assert!(!saucer.can_catch(2.0 + SAUCER_RECAPTURE_DELAY / 2.0));
+        assert!(saucer.can_catch(2.0 + SAUCER_RECAPTURE_DELAY));
+    }
+}
@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+
+use super::common;
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(score_element_hits)
+            .add_system(score_drop_targets)
+            .add_system(score_spinner_spins)
+            .add_system(score_rollover_lanes)
+            .add_system(score_ramp_shots);
+    }
+}
+
+//Points given for each drop target knocked down, and for completing a bank.
+pub const DROP_TARGET_POINTS: u64 = 25;
+pub const DROP_TARGET_BANK_POINTS: u64 = 250;
+
+//Points given for each full turn of a spinner.
+pub const SPINNER_POINTS: u64 = 15;
+
+//Points given for each ball rolling over a lane sensor.
+pub const ROLLOVER_POINTS: u64 = 10;
+
+//Points given for each ball that goes all the way up a ramp.
+pub const RAMP_POINTS: u64 = 200;
+
+//Points given for each push a ball gets from an element.
+pub fn element_points(kind: common::ElementKind) -> u64 {
+    match kind {
+        common::ElementKind::Bumper => 10,
+        common::ElementKind::Pin => 5,
+        common::ElementKind::Target => 50,
+        common::ElementKind::PopBumper => 100,
+        common::ElementKind::Slingshot => 10,
+        common::ElementKind::Kickback => 10,
+        common::ElementKind::Saucer => 300,
+    }
+}
+
+//Every score is multiplied by the bonus multiplier.
+fn score_element_hits(
+    mut element_hits: EventReader<common::ElementHit>,
+    bonus_multiplier: Res<common::BonusMultiplier>,
+    mut score: ResMut<common::Score>,
+) {
+    for hit in element_hits.iter() {
+        score.0 += element_points(hit.kind) * bonus_multiplier.0 as u64;
+    }
+}
+
+fn score_drop_targets(
+    mut drop_target_downs: EventReader<common::DropTargetDown>,
+    mut bank_completes: EventReader<common::DropTargetBankComplete>,
+    bonus_multiplier: Res<common::BonusMultiplier>,
+    mut score: ResMut<common::Score>,
+) {
+    score.0 +=
+        drop_target_downs.iter().count() as u64 * DROP_TARGET_POINTS * bonus_multiplier.0 as u64;
+    score.0 +=
+        bank_completes.iter().count() as u64 * DROP_TARGET_BANK_POINTS * bonus_multiplier.0 as u64;
+}
+
+fn score_spinner_spins(
+    mut spinner_spins: EventReader<common::SpinnerSpin>,
+    bonus_multiplier: Res<common::BonusMultiplier>,
+    mut score: ResMut<common::Score>,
+) {
+    score.0 += spinner_spins.iter().count() as u64 * SPINNER_POINTS * bonus_multiplier.0 as u64;
+}
+
+fn score_rollover_lanes(
+    mut lane_passes: EventReader<common::RolloverLanePassed>,
+    bonus_multiplier: Res<common::BonusMultiplier>,
+    mut score: ResMut<common::Score>,
+) {
+    score.0 += lane_passes.iter().count() as u64 * ROLLOVER_POINTS * bonus_multiplier.0 as u64;
+}
+
+fn score_ramp_shots(
+    mut ramp_shots: EventReader<common::RampShot>,
+    bonus_multiplier: Res<common::BonusMultiplier>,
+    mut score: ResMut<common::Score>,
+) {
+    score.0 += ramp_shots.iter().count() as u64 * RAMP_POINTS * bonus_multiplier.0 as u64;
+}
@@ -0,0 +1,262 @@
+use bevy::audio::AudioPlugin;
+use bevy::ecs::system::SystemState;
+use bevy::gilrs::GilrsPlugin;
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
+use bevy::render::settings::WgpuSettings;
+use bevy::time::TimeUpdateStrategy;
+use bevy::utils::{Duration, Instant};
+use bevy::winit::WinitPlugin;
+use bevy_rapier3d::prelude::*;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::ball;
+use super::is_ball_on_launcher;
+use super::Autopilot;
+use super::Ball;
+use super::Floor;
+use super::Pinball3DPlugin;
+use super::Pinball3DSystems;
+use super::{ActionSystem, InputAction, VirtualActions};
+
+use super::common;
+
+pub const SIMULATION_DT: f32 = 1.0 / 60.0;
+
+//Ticks the launcher is held before it is released again. Three quarters of a full pull at 60 ticks a second.
+const LAUNCH_HOLD_TICKS: u32 = 45;
+//Ticks to wait after a launch before the launcher may fire again.
+const LAUNCH_COOLDOWN_TICKS: u32 = 60;
+
+// Who works the flippers in a headless simulation.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlipperPolicy {
+    // Flippers are never used.
+    Idle,
+    // Both flippers are held up all the time.
+    Hold,
+    // Flippers are pressed and released at random.
+    Random,
+    // The built-in autopilot plays, including the launcher.
+    Autopilot,
+    // Flippers and launcher are worked from outside, through VirtualActions.
+    External,
+}
+
+impl FlipperPolicy {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "idle" => Some(FlipperPolicy::Idle),
+            "hold" => Some(FlipperPolicy::Hold),
+            "random" => Some(FlipperPolicy::Random),
+            "autopilot" => Some(FlipperPolicy::Autopilot),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FlipperPolicy::Idle => "idle",
+            FlipperPolicy::Hold => "hold",
+            FlipperPolicy::Random => "random",
+            FlipperPolicy::Autopilot => "autopilot",
+            FlipperPolicy::External => "external",
+        }
+    }
+}
+
+#[derive(Resource)]
+struct PolicyRng(StdRng);
+
+// A table without window or renderer, stepped manually with a fixed timestep.
+// Time is advanced by exactly dt each tick, so it runs as fast as the machine allows.
+pub struct Simulation {
+    pub app: App,
+    dt: f32,
+    tick: u64,
+    // Seconds all ticks together have advanced time by.
+    elapsed: f64,
+    start: Instant,
+}
+
+impl Simulation {
+    pub fn new(dt: f32, policy: FlipperPolicy, seed: u64) -> Self {
+        let start = Instant::now();
+        let mut app = App::new();
+        app.insert_resource(WgpuSettings {
+            backends: None,
+            ..default()
+        })
+        //The log plugin may only be added once per process, and several simulations can be alive at once.
+        .add_plugins(
+            DefaultPlugins
+                .build()
+                .disable::<WinitPlugin>()
+                .disable::<LogPlugin>()
+                .disable::<AudioPlugin>()
+                .disable::<GilrsPlugin>(),
+        )
+        .add_plugin(Pinball3DPlugin)
+        .insert_resource(TimeUpdateStrategy::ManualInstant(start))
+        .insert_resource(SimulationTimestep(dt))
+        .insert_resource(policy)
+        .insert_resource(PolicyRng(StdRng::seed_from_u64(seed)))
+        .insert_resource(common::GameRng(StdRng::seed_from_u64(seed)))
+        .add_startup_system(use_fixed_timestep.after(Pinball3DSystems::Main));
+
+        match policy {
+            FlipperPolicy::Autopilot => {
+                app.insert_resource(Autopilot(true));
+            }
+            FlipperPolicy::External => {}
+            _ => {
+                app.add_system_to_stage(
+                    CoreStage::PreUpdate,
+                    apply_flipper_policy.before(ActionSystem),
+                )
+                .add_system_to_stage(CoreStage::PreUpdate, auto_launch.before(ActionSystem));
+            }
+        }
+
+        Simulation {
+            app,
+            dt,
+            tick: 0,
+            elapsed: 0.0,
+            start,
+        }
+    }
+
+    pub fn step(&mut self) {
+        self.step_by(self.dt);
+    }
+
+    //Runs one tick that advances time by the given seconds instead of dt, like a frame of the
+    //game that took that long.
+    pub fn step_by(&mut self, seconds: f32) {
+        self.tick += 1;
+        self.elapsed += seconds as f64;
+        let elapsed = Duration::from_secs_f64(self.elapsed);
+        self.app
+            .insert_resource(TimeUpdateStrategy::ManualInstant(self.start + elapsed));
+        self.app.update();
+    }
+
+    //Runs the physics with the variable timestep of the game instead of a fixed one. Every tick
+    //still lasts dt. Call before the first step.
+    pub fn use_game_timestep(&mut self) {
+        self.app.world.remove_resource::<SimulationTimestep>();
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn dt(&self) -> f32 {
+        self.dt
+    }
+
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.elapsed as f32
+    }
+
+    // Drop extra balls onto the floor. Positions are in floor coordinates.
+    // The table must exist, so step at least once before calling this.
+    pub fn spawn_balls_on_floor(&mut self, floor_positions: &[Vec2]) {
+        let world = &mut self.app.world;
+        let floor_transform = *world
+            .query_filtered::<&GlobalTransform, With<Floor>>()
+            .single(world);
+
+        let mut system_state: SystemState<(
+            Commands,
+            ResMut<Assets<Mesh>>,
+            ResMut<Assets<StandardMaterial>>,
+        )> = SystemState::new(world);
+        {
+            let (mut commands, mut meshes, mut materials) = system_state.get_mut(world);
+            for floor_position in floor_positions {
+                let position = floor_transform.transform_point(floor_position.extend(0.04));
+                ball::spawn_single_ball(
+                    &mut commands,
+                    &mut meshes,
+                    &mut materials,
+                    &position,
+                    &ball::MaterialColor(Color::ORANGE_RED),
+                );
+            }
+        }
+        system_state.apply(world);
+    }
+}
+
+#[derive(Resource)]
+struct SimulationTimestep(f32);
+
+fn use_fixed_timestep(
+    mut rapier_config: ResMut<RapierConfiguration>,
+    timestep: Option<Res<SimulationTimestep>>,
+) {
+    let timestep = match timestep {
+        Some(timestep) => timestep,
+        None => return,
+    };
+    rapier_config.timestep_mode = TimestepMode::Fixed {
+        dt: timestep.0,
+        substeps: 2,
+    };
+}
+
+fn apply_flipper_policy(
+    mut virtual_actions: ResMut<VirtualActions>,
+    policy: Res<FlipperPolicy>,
+    mut rng: ResMut<PolicyRng>,
+) {
+    for action in [InputAction::LeftFlipper, InputAction::RightFlipper] {
+        let press = match *policy {
+            FlipperPolicy::Idle => false,
+            FlipperPolicy::Hold => true,
+            FlipperPolicy::Random => {
+                if virtual_actions.0.contains(&action) {
+                    rng.0.gen_range(0.0..1.0) > 0.2
+                } else {
+                    rng.0.gen_range(0.0..1.0) < 0.05
+                }
+            }
+            FlipperPolicy::Autopilot | FlipperPolicy::External => return,
+        };
+
+        virtual_actions.set(action, press);
+    }
+}
+
+//Launch every ball that comes to rest on the launcher.
+fn auto_launch(
+    mut virtual_actions: ResMut<VirtualActions>,
+    query_balls: Query<(&Transform, &Velocity), With<Ball>>,
+    mut hold_ticks: Local<u32>,
+    mut cooldown_ticks: Local<u32>,
+) {
+    if *hold_ticks > 0 {
+        *hold_ticks -= 1;
+        if *hold_ticks == 0 {
+            virtual_actions.set(InputAction::Launch, false);
+            *cooldown_ticks = LAUNCH_COOLDOWN_TICKS;
+        }
+        return;
+    }
+    if *cooldown_ticks > 0 {
+        *cooldown_ticks -= 1;
+        return;
+    }
+
+    if query_balls
+        .iter()
+        .any(|(ball_transform, ball_velocity)| is_ball_on_launcher(ball_transform, ball_velocity))
+    {
+        virtual_actions.set(InputAction::Launch, true);
+        *hold_ticks = LAUNCH_HOLD_TICKS;
+    }
+}
@@ -0,0 +1,286 @@
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy_rapier3d::prelude::*;
+
+use super::Ball;
+use super::Floor;
+use super::HalfHeight;
+
+use super::common;
+
+pub struct SlingshotPlugin;
+
+impl Plugin for SlingshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system_to_stage(StartupStage::PostStartup, spawn_slingshots)
+            .add_system(handle_slingshot_contacts)
+            .add_system(animate_slingshot_kickers);
+    }
+}
+
+//Impulse given to a ball, straight out of the rubber face, when it strikes the slingshot.
+pub const SLINGSHOT_KICK_IMPULSE: f32 = 0.000008;
+//Contact force, in newtons, a ball has to press the rubber face with to fire the slingshot.
+//A ball rolling along the face presses with about a twentieth of this.
+const SLINGSHOT_FORCE_THRESHOLD: f32 = 0.00015;
+//A slingshot does not fire again until this many seconds after it fired.
+const SLINGSHOT_COOLDOWN: f64 = 0.2;
+//The kicker arm is pushed out this far when the slingshot fires, and pulls back over the animation time.
+const KICKER_TRAVEL: f32 = 0.008;
+const KICKER_ANIMATION_SECONDS: f64 = 0.1;
+
+const SLINGSHOT_HALF_HEIGHT: f32 = 0.05;
+const KICKER_THICKNESS: f32 = 0.004;
+
+#[derive(Component)]
+pub struct Slingshot {
+    // Ends of the rubber face, in floor coordinates.
+    face: [Vec2; 2],
+    // Direction out of the rubber face, in floor coordinates.
+    kick_direction: Vec2,
+    // Game time, in seconds, of the last kick.
+    last_fired: f64,
+}
+
+impl Slingshot {
+    //True for a point in front of the rubber face, not beside it or behind the slingshot.
+    fn faces_rubber(&self, point: Vec2) -> bool {
+        let face = self.face[1] - self.face[0];
+        let offset = point - self.face[0];
+        let along = offset.dot(face) / face.length_squared();
+        (0.0..=1.0).contains(&along) && offset.dot(self.kick_direction) > 0.0
+    }
+}
+
+#[derive(Component)]
+struct SlingshotKicker {
+    rest_translation: Vec3,
+}
+
+pub fn spawn_slingshots(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    query_floors: Query<(Entity, &HalfHeight), With<Floor>>,
+) {
+    //Corners in floor coordinates. The rubber face runs from the first to the second corner,
+    //along the inlane down to the flipper. The left one leaves room for the outlane.
+    let slingshots_corners: [[Vec2; 3]; 2] = [
+        [
+            Vec2::new(-0.32, -0.679),
+            Vec2::new(-0.115, -0.784),
+            Vec2::new(-0.32, -0.784),
+        ],
+        [
+            Vec2::new(0.289, -0.695),
+            Vec2::new(0.111, -0.785),
+            Vec2::new(0.289, -0.785),
+        ],
+    ];
+
+    let mut floor = None;
+    let mut floor_half_height = 0.0;
+    for (entity, half_height) in query_floors.iter() {
+        floor = Some(entity);
+        floor_half_height = half_height.0;
+    }
+
+    let material_slingshot = materials.add(Color::CYAN.into());
+    let material_kicker = materials.add(Color::WHITE.into());
+
+    for corners in slingshots_corners {
+        let face = corners[1] - corners[0];
+        let mut kick_direction = face.perp().normalize();
+        if kick_direction.dot(corners[2] - corners[0]) > 0.0 {
+            kick_direction = -kick_direction;
+        }
+
+        let hull_points: Vec<Vec3> = corners
+            .iter()
+            .flat_map(|corner| {
+                [
+                    corner.extend(-SLINGSHOT_HALF_HEIGHT),
+                    corner.extend(SLINGSHOT_HALF_HEIGHT),
+                ]
+            })
+            .collect();
+
+        let kicker_mesh_handle = meshes.add(Mesh::from(shape::Box::new(
+            face.length(),
+            KICKER_THICKNESS,
+            SLINGSHOT_HALF_HEIGHT * 2.0,
+        )));
+        let kicker_rest_translation =
+            ((corners[0] + corners[1]) / 2.0 + kick_direction * KICKER_THICKNESS / 2.0).extend(0.0);
+
+        let slingshot = commands
+            .spawn(PbrBundle {
+                mesh: meshes.add(triangular_prism_mesh(corners, SLINGSHOT_HALF_HEIGHT)),
+                material: material_slingshot.clone(),
+                ..default()
+            })
+            .insert(RigidBody::Fixed)
+            .insert(Collider::convex_hull(&hull_points).expect("slingshot corners span a triangle"))
+            .insert(CollisionGroups {
+                memberships: Group::GROUP_2,
+                filters: Group::GROUP_3,
+            })
+            .insert(ActiveEvents::CONTACT_FORCE_EVENTS)
+            .insert(ContactForceEventThreshold(SLINGSHOT_FORCE_THRESHOLD))
+            .insert(Restitution::coefficient(0.5))
+            .insert(TransformBundle::from(Transform::from_xyz(
+                0.0,
+                0.0,
+                floor_half_height + SLINGSHOT_HALF_HEIGHT,
+            )))
+            .insert(Slingshot {
+                face: [corners[0], corners[1]],
+                kick_direction,
+                last_fired: f64::NEG_INFINITY,
+            })
+            .with_children(|children| {
+                children
+                    .spawn(PbrBundle {
+                        mesh: kicker_mesh_handle,
+                        material: material_kicker.clone(),
+                        transform: Transform {
+                            translation: kicker_rest_translation,
+                            rotation: Quat::from_rotation_z(face.y.atan2(face.x)),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .insert(SlingshotKicker {
+                        rest_translation: kicker_rest_translation,
+                    });
+            })
+            .id();
+
+        commands.entity(floor.unwrap()).add_child(slingshot);
+    }
+}
+
+//Upright prism with a triangle as its cross section, centred on z = 0.
+fn triangular_prism_mesh(corners: [Vec2; 3], half_height: f32) -> Mesh {
+    //Walk the corners counter clockwise, seen from above.
+    let mut corners = corners;
+    if (corners[1] - corners[0]).perp_dot(corners[2] - corners[0]) < 0.0 {
+        corners.swap(1, 2);
+    }
+
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    for (z, normal, order) in [
+        (half_height, [0.0, 0.0, 1.0], [0, 1, 2]),
+        (-half_height, [0.0, 0.0, -1.0], [0, 2, 1]),
+    ] {
+        let first = positions.len() as u32;
+        for i in order {
+            positions.push(corners[i].extend(z).into());
+            normals.push(normal);
+        }
+        indices.extend([first, first + 1, first + 2]);
+    }
+
+    for i in 0..3 {
+        let a = corners[i];
+        let b = corners[(i + 1) % 3];
+        let normal = Vec2::new(b.y - a.y, a.x - b.x).normalize().extend(0.0);
+        let first = positions.len() as u32;
+        for position in [
+            a.extend(-half_height),
+            b.extend(-half_height),
+            b.extend(half_height),
+            a.extend(half_height),
+        ] {
+            positions.push(position.into());
+            normals.push(normal.into());
+        }
+        indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+
+    let uvs = vec![[0.0, 0.0]; positions.len()];
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+//Kick a ball that strikes the rubber face hard enough out along the face normal.
+//Contact force events only come for contacts above the slingshot's threshold. A ball that hits
+//the back or bottom of the slingshot is not in front of the rubber face, and just bounces off.
+fn handle_slingshot_contacts(
+    time: Res<Time>,
+    mut contact_force_events: EventReader<ContactForceEvent>,
+    mut query_slingshots: Query<(&mut Slingshot, &GlobalTransform)>,
+    mut query_balls: Query<(&mut ExternalImpulse, &Transform), With<Ball>>,
+    mut element_hits: EventWriter<common::ElementHit>,
+) {
+    for contact_force_event in contact_force_events.iter() {
+        let h1 = contact_force_event.collider1;
+        let h2 = contact_force_event.collider2;
+        for (entity_slingshot, entity_ball) in [(h1, h2), (h2, h1)] {
+            let (mut slingshot, slingshot_transform) =
+                match query_slingshots.get_mut(entity_slingshot) {
+                    Ok(slingshot) => slingshot,
+                    Err(_) => continue,
+                };
+            let (mut external_impulse, ball_transform) = match query_balls.get_mut(entity_ball) {
+                Ok(ball) => ball,
+                Err(_) => continue,
+            };
+
+            let ball_position = slingshot_transform
+                .affine()
+                .inverse()
+                .transform_point3(ball_transform.translation);
+            if !slingshot.faces_rubber(ball_position.truncate()) {
+                continue;
+            }
+
+            let now = time.elapsed_seconds_f64();
+            if now - slingshot.last_fired < SLINGSHOT_COOLDOWN {
+                continue;
+            }
+            slingshot.last_fired = now;
+
+            let (_scale, rotation, _translation) =
+                slingshot_transform.to_scale_rotation_translation();
+            let impulse = rotation * slingshot.kick_direction.extend(0.0) * SLINGSHOT_KICK_IMPULSE;
+            external_impulse.impulse += impulse;
+            element_hits.send(common::ElementHit {
+                kind: common::ElementKind::Slingshot,
+                element: entity_slingshot,
+                ball: entity_ball,
+                impulse,
+            });
+        }
+    }
+}
+
+//The kicker arm snaps out of the rubber face when the slingshot fires, then pulls back.
+fn animate_slingshot_kickers(
+    time: Res<Time>,
+    query_slingshots: Query<&Slingshot>,
+    mut query_kickers: Query<(&SlingshotKicker, &Parent, &mut Transform)>,
+) {
+    for (kicker, parent, mut kicker_transform) in query_kickers.iter_mut() {
+        let slingshot = match query_slingshots.get(parent.get()) {
+            Ok(slingshot) => slingshot,
+            Err(_) => continue,
+        };
+        let progress =
+            (time.elapsed_seconds_f64() - slingshot.last_fired) / KICKER_ANIMATION_SECONDS;
+        let extended = (1.0 - progress).clamp(0.0, 1.0) as f32;
+
+        let translation = kicker.rest_translation
+            + (slingshot.kick_direction * KICKER_TRAVEL * extended).extend(0.0);
+        if kicker_transform.translation != translation {
+            kicker_transform.translation = translation;
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::common;
+use super::Floor;
+use super::HalfHeight;
+
+pub struct SpinnerPlugin;
+
+impl Plugin for SpinnerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system_to_stage(StartupStage::PostStartup, spawn_spinners)
+            .add_system(count_spinner_turns);
+    }
+}
+
+const SPINNER_HALF_WIDTH: f32 = 0.02;
+const SPINNER_HALF_THICKNESS: f32 = 0.0015;
+const SPINNER_HALF_HEIGHT: f32 = 0.025;
+//Height of the joint axis above the floor. The ball only catches the bottom of the plate.
+const SPINNER_AXIS_HEIGHT: f32 = 0.06;
+
+// A plate hanging on a free revolute joint about the floor x axis.
+#[derive(Component)]
+pub struct Spinner {
+    // Joint angle seen last frame, in radians. 0 is hanging straight down.
+    angle: f32,
+    // Angle turned since the last full turn was counted, signed by direction.
+    turned: f32,
+}
+
+pub fn spawn_spinners(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    query_floors: Query<(Entity, &HalfHeight), With<Floor>>,
+) {
+    //In the launcher lane, so every launch sends the ball through it.
+    let spinners_pos: [Vec2; 1] = [Vec2::new(0.335, -0.6)];
+
+    let mut floor = None;
+    let mut floor_half_height = 0.0;
+    for (entity, half_height) in query_floors.iter() {
+        floor = Some(entity);
+        floor_half_height = half_height.0;
+    }
+
+    let spinner_mesh_handle: Handle<Mesh> = meshes.add(Mesh::from(shape::Box::new(
+        SPINNER_HALF_WIDTH * 2.0,
+        SPINNER_HALF_THICKNESS * 2.0,
+        SPINNER_HALF_HEIGHT * 2.0,
+    )));
+    let material_spinner = materials.add(Color::GOLD.into());
+
+    for spinner_pos in spinners_pos {
+        let anchor_pos = spinner_pos.extend(floor_half_height + SPINNER_AXIS_HEIGHT);
+
+        let spinner_anchor = commands
+            .spawn(RigidBody::Fixed)
+            .insert(TransformBundle::from(Transform::from_translation(
+                anchor_pos,
+            )))
+            .id();
+
+        //No limits, the plate turns all the way round as often as the ball makes it.
+        let joint = RevoluteJointBuilder::new(Vec3::X)
+            .local_anchor1(Vec3::ZERO)
+            .local_anchor2(Vec3::new(0.0, 0.0, SPINNER_HALF_HEIGHT));
+
+        let spinner = commands
+            .spawn(PbrBundle {
+                mesh: spinner_mesh_handle.clone(),
+                material: material_spinner.clone(),
+                ..default()
+            })
+            .insert(RigidBody::Dynamic)
+            .insert(Sleeping::disabled())
+            .insert(Ccd::enabled())
+            .insert(Damping {
+                linear_damping: 0.0,
+                angular_damping: 0.5,
+            })
+            .with_children(|children| {
+                children
+                    .spawn(Collider::cuboid(
+                        SPINNER_HALF_WIDTH,
+                        SPINNER_HALF_THICKNESS,
+                        SPINNER_HALF_HEIGHT,
+                    ))
+                    .insert(CollisionGroups {
+                        memberships: Group::GROUP_2,
+                        filters: Group::GROUP_3,
+                    });
+                children.spawn(ImpulseJoint::new(spinner_anchor, joint));
+            })
+            .insert(TransformBundle::from(Transform::from_translation(
+                anchor_pos - Vec3::new(0.0, 0.0, SPINNER_HALF_HEIGHT),
+            )))
+            .insert(Spinner {
+                angle: 0.0,
+                turned: 0.0,
+            })
+            .id();
+
+        commands
+            .entity(floor.unwrap())
+            .push_children(&[spinner_anchor, spinner]);
+    }
+}
+
+//Joint angle of a spinner from its rotation in floor coordinates. A turn of the angle about x
+//takes the hanging direction -z to (0, sin, -cos).
+fn spinner_joint_angle(rotation: Quat) -> f32 {
+    let hanging = rotation * Vec3::NEG_Z;
+    hanging.y.atan2(-hanging.z)
+}
+
+//Follows the joint angle of every spinner and sends an event for each full turn, either way round.
+fn count_spinner_turns(
+    mut query_spinners: Query<(Entity, &mut Spinner, &Transform)>,
+    mut spinner_spins: EventWriter<common::SpinnerSpin>,
+) {
+    let full_turn = std::f32::consts::TAU;
+    for (entity_spinner, mut spinner, transform) in query_spinners.iter_mut() {
+        let angle = spinner_joint_angle(transform.rotation);
+        //The angle wraps around at half a turn, so take the shorter way from the last angle.
+        let change = (angle - spinner.angle + std::f32::consts::PI).rem_euclid(full_turn)
+            - std::f32::consts::PI;
+        spinner.angle = angle;
+        spinner.turned += change;
+
+        while spinner.turned.abs() >= full_turn {
+            spinner.turned -= full_turn * spinner.turned.signum();
+            spinner_spins.send(common::SpinnerSpin {
+                spinner: entity_spinner,
+            });
+        }
+    }
+}
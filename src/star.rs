@@ -5,6 +5,7 @@ use rand::Rng;
 
 use super::ball;
 use super::bumper;
+use super::is_ball_in_star_collector;
 use super::spawn_single_ball;
 use super::Ball;
 use super::Floor;
@@ -28,7 +29,7 @@ struct Star;
 #[derive(Default, Component)]
 struct CollectorSensor;
 
-fn spawn_star(
+pub fn spawn_star(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
@@ -171,46 +172,6 @@ fn spawn_star(
         .insert(common::DespawnInEndGame)
         .id();
 
-    //Starramp
-    let starramp_height = 0.06;
-    let starramp_length = 0.16;
-    let starramp_width = 0.1;
-    let starramp_position = Vec3::new(-0.1, 0.135, 0.02);
-    let starramp_mesh_handle: Handle<Mesh> = meshes.add(Mesh::from(shape::Box::new(
-        starramp_length,
-        starramp_width,
-        starramp_height,
-    )));
-    let starramp_material = materials.add(Color::rgba(1.0, 1.0, 0.0, 0.8).into());
-
-    let starramp = commands
-        .spawn(PbrBundle {
-            mesh: starramp_mesh_handle.clone(),
-            material: starramp_material.clone(),
-            ..default()
-        })
-        .insert(RigidBody::Fixed)
-        .insert(Collider::cuboid(
-            starramp_length / 2.0,
-            starramp_width / 2.0,
-            starramp_height / 2.0,
-        ))
-        .insert(CollisionGroups {
-            memberships: Group::GROUP_1,
-            filters: Group::GROUP_3,
-        })
-        .insert(TransformBundle::from(Transform {
-            translation: Vec3::new(
-                starramp_position.x,
-                starramp_position.y,
-                starramp_position.z,
-            ),
-            rotation: Quat::from_rotation_z(std::f32::consts::PI / 4.0)
-                * Quat::from_rotation_y(-std::f32::consts::PI / 6.0),
-            ..default()
-        }))
-        .id();
-
     let mut floor = None;
     for (entity, _half_height) in query_floors.iter() {
         floor = Some(entity);
@@ -220,7 +181,6 @@ fn spawn_star(
         collector_collider,
         oneway_collector_lid,
         collector_sensor,
-        starramp,
     ]);
 }
 
@@ -232,6 +192,7 @@ fn handle_star_ball_sensor_events(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut end_game: ResMut<common::EndGame>,
+    mut rng: ResMut<common::GameRng>,
 ) {
     for contact_event in contact_events.iter() {
         for sensor_entity in query_collector_sensors.iter() {
@@ -241,7 +202,7 @@ fn handle_star_ball_sensor_events(
                     for (entity_ball, mut collision_group) in query_balls.iter_mut() {
                         if h1 == &entity_ball || h2 == &entity_ball {
                             //Add GROUP_5 to filters. This will activate collision between the ball and the one way gate collider
-                            if (collision_group.filters & Group::GROUP_5) == Group::NONE {
+                            if !is_ball_in_star_collector(&collision_group) {
                                 collision_group.filters = Group::GROUP_1
                                     | Group::GROUP_2
                                     | Group::GROUP_3
@@ -260,8 +221,7 @@ fn handle_star_ball_sensor_events(
                             Color::GREEN,
                             Color::PINK,
                         ];
-                        let mut rng = rand::thread_rng();
-                        let chosen_index = rng.gen_range(0..5);
+                        let chosen_index = rng.0.gen_range(0..5);
                         spawn_single_ball(
                             &mut commands,
                             &mut meshes,
@@ -274,8 +234,7 @@ fn handle_star_ball_sensor_events(
                         //  Set endgame resource.
                         let mut balls_group5_counter = 0;
                         for (_entity, collision_group) in query_balls.iter() {
-                            //Add GROUP_5 to filters. This will activate collision between the ball and the one way gate collider
-                            if (collision_group.filters & Group::GROUP_5) == Group::GROUP_5 {
+                            if is_ball_in_star_collector(collision_group) {
                                 balls_group5_counter += 1;
                             }
                         }
@@ -297,6 +256,10 @@ fn despawn_collector_when_endgame(
     end_game: Res<common::EndGame>,
     mut done: Local<bool>,
 ) {
+    //A restarted game can end again.
+    if !end_game.0 {
+        *done = false;
+    }
     if !*done {
         if end_game.0 == true {
             //  Despawn collector, lid, sensor and right-down bumper.
@@ -6,6 +6,8 @@ use super::Ball;
 use super::Floor;
 use super::HalfHeight;
 
+use super::common;
+
 pub struct TargetPlugin;
 
 impl Plugin for TargetPlugin {
@@ -15,10 +17,13 @@ impl Plugin for TargetPlugin {
     }
 }
 
+//Impulse given to a stopped ball to send it up and to the right, into the entry of the star ramp.
+pub const TARGET_PUSH_IMPULSE: f32 = 0.000013;
+
 #[derive(Component)]
 struct Target;
 
-fn spawn_target(
+pub fn spawn_target(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
@@ -77,6 +82,7 @@ fn handle_target_events(
     query_targets: Query<Entity, With<Target>>,
     mut query_balls: Query<(Entity, &mut ExternalImpulse, &mut Velocity), With<Ball>>,
     mut contact_events: EventReader<CollisionEvent>,
+    mut element_hits: EventWriter<common::ElementHit>,
 ) {
     for contact_event in contact_events.iter() {
         for entity in query_targets.iter() {
@@ -87,9 +93,14 @@ fn handle_target_events(
                     {
                         if h1 == &entity_ball || h2 == &entity_ball {
                             velocity.linvel = Vec3::new(0.0, 0.0, 0.0);
-                            external_impulse.impulse = external_impulse
-                                .impulse
-                                .add(Vec3::new(1.0, 1.0, 0.0) * 0.000013);
+                            let impulse = Vec3::new(1.0, 1.0, 0.0) * TARGET_PUSH_IMPULSE;
+                            external_impulse.impulse = external_impulse.impulse.add(impulse);
+                            element_hits.send(common::ElementHit {
+                                kind: common::ElementKind::Target,
+                                element: entity,
+                                ball: entity_ball,
+                                impulse,
+                            });
                         }
                     }
                 }
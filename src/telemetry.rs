@@ -0,0 +1,216 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::Serialize;
+
+use super::Ball;
+use super::Flipper;
+use super::Wall;
+
+use super::common;
+
+// Telemetry is switched on by pointing this environment variable at an output file.
+// A path ending in ".csv" gives CSV, anything else gives JSON lines.
+pub const TELEMETRY_ENV_VAR: &str = "PINBALL3D_TELEMETRY";
+
+pub struct TelemetryPlugin;
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        let path = match std::env::var(TELEMETRY_ENV_VAR) {
+            Ok(path) => path,
+            Err(_) => return,
+        };
+
+        match TelemetrySink::create(&path) {
+            Ok(sink) => {
+                app.insert_resource(sink)
+                    .add_system_to_stage(CoreStage::PostUpdate, write_telemetry);
+            }
+            Err(error) => warn!("Could not open telemetry file {}: {}", path, error),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TelemetryFormat {
+    Jsonl,
+    Csv,
+}
+
+#[derive(Resource)]
+pub struct TelemetrySink {
+    writer: BufWriter<File>,
+    format: TelemetryFormat,
+    // Game time, in seconds, of the last flush.
+    last_flush: f64,
+}
+
+//The file is flushed at most this often, and when the app exits.
+const TELEMETRY_FLUSH_SECONDS: f64 = 1.0;
+
+//Ball samples fill the position and velocity columns, hits the impulse columns, and contacts the
+//position of the ball.
+const CSV_HEADER: &str = "tick,time,record,entity,element,ball,x,y,z,vx,vy,vz,ix,iy,iz";
+
+// One line of telemetry. Fields a record does not have are left out of JSON and empty in CSV.
+#[derive(Serialize)]
+struct TelemetryRecord {
+    tick: u64,
+    time: f64,
+    record: &'static str,
+    entity: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    element: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ball: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    position: Option<[f32; 3]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    velocity: Option<[f32; 3]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    impulse: Option<[f32; 3]>,
+}
+
+impl TelemetryRecord {
+    fn csv_line(&self) -> String {
+        let vector = |vector: Option<[f32; 3]>| match vector {
+            Some([x, y, z]) => format!("{},{},{}", x, y, z),
+            None => ",,".to_string(),
+        };
+        format!(
+            "{},{},{},{},{},{},{},{},{}",
+            self.tick,
+            self.time,
+            self.record,
+            self.entity,
+            self.element.unwrap_or_default(),
+            self.ball.as_deref().unwrap_or_default(),
+            vector(self.position),
+            vector(self.velocity),
+            vector(self.impulse)
+        )
+    }
+}
+
+impl TelemetrySink {
+    pub fn create(path: &str) -> std::io::Result<Self> {
+        let format = if path.ends_with(".csv") {
+            TelemetryFormat::Csv
+        } else {
+            TelemetryFormat::Jsonl
+        };
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        if format == TelemetryFormat::Csv {
+            writeln!(writer, "{}", CSV_HEADER)?;
+        }
+
+        Ok(TelemetrySink {
+            writer,
+            format,
+            last_flush: 0.0,
+        })
+    }
+
+    fn write_record(&mut self, record: &TelemetryRecord) {
+        let line = match self.format {
+            TelemetryFormat::Jsonl => match serde_json::to_string(record) {
+                Ok(line) => line,
+                Err(error) => {
+                    warn!("Could not serialize telemetry: {}", error);
+                    return;
+                }
+            },
+            TelemetryFormat::Csv => record.csv_line(),
+        };
+        if let Err(error) = writeln!(self.writer, "{}", line) {
+            warn!("Could not write telemetry: {}", error);
+        }
+    }
+
+    fn flush(&mut self, now: f64) {
+        self.last_flush = now;
+        if let Err(error) = self.writer.flush() {
+            warn!("Could not flush telemetry: {}", error);
+        }
+    }
+}
+
+fn write_telemetry(
+    query_balls: Query<(Entity, &Transform, &Velocity), With<Ball>>,
+    query_walls: Query<(), With<Wall>>,
+    query_flippers: Query<(), With<Flipper>>,
+    mut element_hits: EventReader<common::ElementHit>,
+    mut contact_events: EventReader<CollisionEvent>,
+    mut app_exits: EventReader<AppExit>,
+    mut sink: ResMut<TelemetrySink>,
+    time: Res<Time>,
+    mut tick: Local<u64>,
+) {
+    *tick += 1;
+    let elapsed = time.elapsed_seconds_f64();
+
+    for (entity_ball, ball_transform, ball_velocity) in query_balls.iter() {
+        sink.write_record(&TelemetryRecord {
+            tick: *tick,
+            time: elapsed,
+            record: "ball",
+            entity: format!("{:?}", entity_ball),
+            element: None,
+            ball: None,
+            position: Some(ball_transform.translation.to_array()),
+            velocity: Some(ball_velocity.linvel.to_array()),
+            impulse: None,
+        });
+    }
+    for hit in element_hits.iter() {
+        sink.write_record(&TelemetryRecord {
+            tick: *tick,
+            time: elapsed,
+            record: "hit",
+            entity: format!("{:?}", hit.element),
+            element: Some(hit.kind.name()),
+            ball: Some(format!("{:?}", hit.ball)),
+            position: None,
+            velocity: None,
+            impulse: Some(hit.impulse.to_array()),
+        });
+    }
+    //Walls and flippers give no ElementHit, their collisions are logged as contacts.
+    for contact_event in contact_events.iter() {
+        if let CollisionEvent::Started(h1, h2, _event_flag) = contact_event {
+            for (entity, entity_ball) in [(h1, h2), (h2, h1)] {
+                let element = if query_walls.contains(*entity) {
+                    "wall"
+                } else if query_flippers.contains(*entity) {
+                    "flipper"
+                } else {
+                    continue;
+                };
+                let ball_transform = match query_balls.get(*entity_ball) {
+                    Ok((_entity, ball_transform, _velocity)) => ball_transform,
+                    Err(_) => continue,
+                };
+                sink.write_record(&TelemetryRecord {
+                    tick: *tick,
+                    time: elapsed,
+                    record: "contact",
+                    entity: format!("{:?}", entity),
+                    element: Some(element),
+                    ball: Some(format!("{:?}", entity_ball)),
+                    position: Some(ball_transform.translation.to_array()),
+                    velocity: None,
+                    impulse: None,
+                });
+            }
+        }
+    }
+
+    if app_exits.iter().count() > 0 || elapsed - sink.last_flush >= TELEMETRY_FLUSH_SECONDS {
+        sink.flush(elapsed);
+    }
+}
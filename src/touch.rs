@@ -0,0 +1,116 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::is_ball_on_launcher;
+use super::Ball;
+use super::{ActionState, ActionSystem, DeviceActionSystem, InputAction};
+
+pub struct TouchControlsPlugin;
+
+impl Plugin for TouchControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchControls>().add_system_to_stage(
+            CoreStage::PreUpdate,
+            touch_actions.label(DeviceActionSystem).after(ActionSystem),
+        );
+    }
+}
+
+//Touches starting right of this and below this, as a share of the window, can pull the plunger
+//while a ball is on the launcher.
+const PLUNGER_AREA_MIN_X: f32 = 0.5;
+const PLUNGER_AREA_MIN_Y: f32 = 0.6;
+//Dragging down this share of the window height turns the touch into a plunger pull.
+const PLUNGER_DRAG: f32 = 0.05;
+//Dragging down this share of the window height pulls the plunger all the way back.
+const PLUNGER_FULL_DRAG: f32 = 0.25;
+//A touch in the plunger area that has not been dragged down within this many seconds holds the
+//right flipper instead.
+const PLUNGER_DECIDE_SECONDS: f64 = 0.15;
+
+// What every touch does. A touch starting in the plunger area does nothing until it is known
+// whether it pulls the plunger or holds the flipper, so a plunger pull never flicks the flipper.
+// With no ball on the launcher there is nothing to pull, and the flipper goes up at once.
+#[derive(Resource, Default)]
+pub struct TouchControls {
+    plunger_touches: HashSet<u64>,
+    flipper_touches: HashSet<u64>,
+    // Touches in the plunger area not yet decided, with the game time, in seconds, they started.
+    undecided_touches: HashMap<u64, f64>,
+}
+
+//Every touch is handled on its own, so both flippers can be held with two fingers.
+//Touch positions are in window pixels from the top left corner.
+fn touch_actions(
+    time: Res<Time>,
+    windows: Res<Windows>,
+    touches: Res<Touches>,
+    mut touch_controls: ResMut<TouchControls>,
+    mut action_state: ResMut<ActionState>,
+    query_balls: Query<(&Transform, &Velocity), With<Ball>>,
+) {
+    let window_size = match windows.get_primary() {
+        Some(window) => Vec2::new(window.width(), window.height()),
+        None => return,
+    };
+    let now = time.elapsed_seconds_f64();
+    let ball_on_launcher = query_balls
+        .iter()
+        .any(|(ball_transform, ball_velocity)| is_ball_on_launcher(ball_transform, ball_velocity));
+
+    for touch in touches.iter_just_cancelled() {
+        touch_controls.plunger_touches.remove(&touch.id());
+        touch_controls.flipper_touches.remove(&touch.id());
+        touch_controls.undecided_touches.remove(&touch.id());
+    }
+    for touch in touches.iter_just_released() {
+        touch_controls.plunger_touches.remove(&touch.id());
+        touch_controls.flipper_touches.remove(&touch.id());
+        //A quick tap in the plunger area is still a flick of the flipper.
+        if touch_controls
+            .undecided_touches
+            .remove(&touch.id())
+            .is_some()
+        {
+            action_state.press(InputAction::RightFlipper);
+        }
+    }
+
+    for touch in touches.iter() {
+        let start = touch.start_position() / window_size;
+        let dragged_down = (touch.position().y - touch.start_position().y) / window_size.y;
+        let id = touch.id();
+        if !touch_controls.plunger_touches.contains(&id)
+            && !touch_controls.flipper_touches.contains(&id)
+        {
+            let in_plunger_area = start.x > PLUNGER_AREA_MIN_X && start.y > PLUNGER_AREA_MIN_Y;
+            if !in_plunger_area
+                || (!ball_on_launcher && !touch_controls.undecided_touches.contains_key(&id))
+            {
+                touch_controls.flipper_touches.insert(id);
+            } else if dragged_down > PLUNGER_DRAG {
+                touch_controls.undecided_touches.remove(&id);
+                touch_controls.plunger_touches.insert(id);
+            } else {
+                let started = *touch_controls.undecided_touches.entry(id).or_insert(now);
+                if now - started < PLUNGER_DECIDE_SECONDS {
+                    continue;
+                }
+                touch_controls.undecided_touches.remove(&id);
+                touch_controls.flipper_touches.insert(id);
+            }
+        }
+        if touch_controls.plunger_touches.contains(&id) {
+            action_state.pull_plunger((dragged_down / PLUNGER_FULL_DRAG).clamp(0.0, 1.0));
+            continue;
+        }
+
+        if start.x < 0.5 {
+            action_state.press(InputAction::LeftFlipper);
+        } else {
+            action_state.press(InputAction::RightFlipper);
+        }
+    }
+}
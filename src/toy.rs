@@ -0,0 +1,330 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::common;
+use super::Floor;
+use super::HalfHeight;
+use super::Pinball3DStages;
+
+pub struct ToyPlugin;
+
+impl Plugin for ToyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system_to_stage(StartupStage::PostStartup, spawn_toys)
+            .add_system_to_stage(Pinball3DStages::Kinematics, toy_movement);
+    }
+}
+
+const DISC_HALF_HEIGHT: f32 = 0.002;
+//How far the top of a disc stands above the floor, so the ball rolls on the disc and not the floor.
+const DISC_RISE: f32 = 0.0005;
+const MOVING_TARGET_HALF_SIZE: Vec3 = Vec3::new(0.02, 0.004, 0.015);
+
+// What a toy is.
+#[derive(Clone, Copy, Debug)]
+pub enum ToyShape {
+    // Disc set into the floor, its top just above the floor.
+    Disc { radius: f32 },
+    // Upright target standing on the floor.
+    MovingTarget,
+}
+
+// How a toy moves. Speeds are per second of physics time, so toys stop while the game is paused.
+#[derive(Clone, Debug)]
+pub enum ToyTrack {
+    // Turns in place about the floor normal, in radians per second. Positive is counterclockwise.
+    Spin { angular_speed: f32 },
+    // Goes back and forth along the path through the points, in metres per second. The points are
+    // relative to the toy position, and the toy starts at the first one.
+    Path { points: Vec<Vec2>, speed: f32 },
+}
+
+// A toy in the table data.
+#[derive(Clone, Debug)]
+pub struct ToySpec {
+    // Position in floor coordinates.
+    pub position: Vec2,
+    pub shape: ToyShape,
+    pub track: ToyTrack,
+}
+
+impl ToySpec {
+    //A path toy needs a point to start at, and a disc a size.
+    pub fn validate(&self) -> Result<(), String> {
+        if let ToyShape::Disc { radius } = self.shape {
+            if radius <= 0.0 {
+                return Err(format!("disc radius {} is not positive", radius));
+            }
+        }
+        match &self.track {
+            ToyTrack::Spin { angular_speed } if !angular_speed.is_finite() => {
+                Err(format!("angular speed {} is not finite", angular_speed))
+            }
+            ToyTrack::Path { points, .. } if points.is_empty() => {
+                Err("path has no points".to_string())
+            }
+            ToyTrack::Path { speed, .. } if !speed.is_finite() => {
+                Err(format!("path speed {} is not finite", speed))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Toy {
+    pub track: ToyTrack,
+    // Position the track is relative to, in floor coordinates.
+    origin: Vec2,
+    // Distance travelled along a path, there and back counted together.
+    progress: f32,
+}
+
+//The toys of the table. Add an entry to get another toy.
+fn table_toys() -> Vec<ToySpec> {
+    vec![
+        //Spinning disc between the pins, turning balls that roll over it aside.
+        ToySpec {
+            position: Vec2::new(0.0, -0.2),
+            shape: ToyShape::Disc { radius: 0.045 },
+            track: ToyTrack::Spin { angular_speed: 6.0 },
+        },
+        //Target going back and forth above the flippers.
+        ToySpec {
+            position: Vec2::new(0.0, -0.53),
+            shape: ToyShape::MovingTarget,
+            track: ToyTrack::Path {
+                points: vec![Vec2::new(-0.12, 0.0), Vec2::new(0.12, 0.0)],
+                speed: 0.1,
+            },
+        },
+    ]
+}
+
+pub fn spawn_toys(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    query_floors: Query<(Entity, &HalfHeight), With<Floor>>,
+) {
+    let mut floor = None;
+    let mut floor_half_height = 0.0;
+    for (entity, half_height) in query_floors.iter() {
+        floor = Some(entity);
+        floor_half_height = half_height.0;
+    }
+
+    for toy_spec in table_toys() {
+        if let Err(error) = toy_spec.validate() {
+            warn!("Toy left out: {}", error);
+            continue;
+        }
+        let start = match &toy_spec.track {
+            ToyTrack::Spin { .. } => toy_spec.position,
+            ToyTrack::Path { points, .. } => toy_spec.position + points[0],
+        };
+
+        let toy = match toy_spec.shape {
+            ToyShape::Disc { radius } => commands
+                .spawn(SpatialBundle::from_transform(Transform::from_xyz(
+                    start.x,
+                    start.y,
+                    floor_half_height + DISC_RISE - DISC_HALF_HEIGHT,
+                )))
+                //The cylinder axis is local y, turned to point out of the floor.
+                .insert(Collider::compound(vec![(
+                    Vec3::ZERO,
+                    Quat::from_rotation_x(std::f32::consts::PI / 2.0),
+                    Collider::cylinder(DISC_HALF_HEIGHT, radius),
+                )]))
+                //The disc is part of the floor, and its grip drags the ball along.
+                .insert(CollisionGroups {
+                    memberships: Group::GROUP_1,
+                    filters: Group::GROUP_3,
+                })
+                .insert(Friction {
+                    coefficient: 0.8,
+                    combine_rule: CoefficientCombineRule::Max,
+                })
+                .with_children(|children| {
+                    children.spawn(PbrBundle {
+                        mesh: meshes.add(Mesh::from(shape::Circle {
+                            radius,
+                            vertices: 48,
+                        })),
+                        material: materials.add(Color::MIDNIGHT_BLUE.into()),
+                        transform: Transform::from_xyz(0.0, 0.0, DISC_HALF_HEIGHT + 0.0001),
+                        ..default()
+                    });
+                    //A stripe across the disc shows it turning.
+                    children.spawn(PbrBundle {
+                        mesh: meshes.add(Mesh::from(shape::Box::new(radius * 2.0, 0.006, 0.0002))),
+                        material: materials.add(Color::WHITE.into()),
+                        transform: Transform::from_xyz(0.0, 0.0, DISC_HALF_HEIGHT + 0.0002),
+                        ..default()
+                    });
+                })
+                .id(),
+            ToyShape::MovingTarget => commands
+                .spawn(PbrBundle {
+                    mesh: meshes.add(Mesh::from(shape::Box::new(
+                        MOVING_TARGET_HALF_SIZE.x * 2.0,
+                        MOVING_TARGET_HALF_SIZE.y * 2.0,
+                        MOVING_TARGET_HALF_SIZE.z * 2.0,
+                    ))),
+                    material: materials.add(Color::LIME_GREEN.into()),
+                    transform: Transform::from_xyz(
+                        start.x,
+                        start.y,
+                        floor_half_height + MOVING_TARGET_HALF_SIZE.z,
+                    ),
+                    ..default()
+                })
+                .insert(Collider::cuboid(
+                    MOVING_TARGET_HALF_SIZE.x,
+                    MOVING_TARGET_HALF_SIZE.y,
+                    MOVING_TARGET_HALF_SIZE.z,
+                ))
+                .insert(CollisionGroups {
+                    memberships: Group::GROUP_2,
+                    filters: Group::GROUP_3,
+                })
+                .insert(Restitution::coefficient(0.5))
+                .id(),
+        };
+
+        commands
+            .entity(toy)
+            .insert(RigidBody::KinematicVelocityBased)
+            .insert(Velocity::zero())
+            .insert(Toy {
+                track: toy_spec.track,
+                origin: toy_spec.position,
+                progress: 0.0,
+            });
+
+        commands.entity(floor.unwrap()).add_child(toy);
+    }
+}
+
+//Point at a distance along a path that goes through the points and back again. There must be at
+//least one point.
+fn path_position(points: &[Vec2], distance: f32) -> Vec2 {
+    let length: f32 = points
+        .windows(2)
+        .map(|pair| pair[0].distance(pair[1]))
+        .sum();
+    if length <= 0.0 {
+        return points[0];
+    }
+    let mut distance = distance.rem_euclid(2.0 * length);
+    if distance > length {
+        distance = 2.0 * length - distance;
+    }
+    for pair in points.windows(2) {
+        let segment_length = pair[0].distance(pair[1]);
+        if distance <= segment_length && segment_length > 0.0 {
+            return pair[0].lerp(pair[1], distance / segment_length);
+        }
+        distance -= segment_length;
+    }
+    points[points.len() - 1]
+}
+
+//Moves every toy along its track by the time the physics advances, and gives it the velocity
+//that takes it there in one physics step, so balls are pushed with the speed the toy really has.
+fn toy_movement(
+    time: Res<Time>,
+    rapier_config: Res<RapierConfiguration>,
+    query_floors: Query<&GlobalTransform, With<Floor>>,
+    mut query_toys: Query<(&mut Toy, &Transform, &mut Velocity)>,
+) {
+    let dt = common::physics_dt(&rapier_config, &time);
+    let floor_transform = match query_floors.iter().next() {
+        Some(floor_transform) => floor_transform,
+        None => return,
+    };
+    let (_scale, floor_rotation, _translation) = floor_transform.to_scale_rotation_translation();
+
+    for (mut toy, transform, mut velocity) in query_toys.iter_mut() {
+        //Paused, or the game has not advanced: the toys stand still.
+        if dt <= 0.0 {
+            *velocity = Velocity::zero();
+            continue;
+        }
+        match &toy.track {
+            ToyTrack::Spin { angular_speed } => {
+                velocity.angvel = floor_rotation * Vec3::Z * *angular_speed;
+            }
+            ToyTrack::Path { points, speed } => {
+                let progress = toy.progress + speed * dt;
+                let target = toy.origin + path_position(points, progress);
+                let current = transform.translation.truncate();
+                velocity.linvel = floor_rotation * ((target - current) / dt).extend(0.0);
+                toy.progress = progress;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 1e-5, "{} is not {}", a, b);
+    }
+
+    #[test]
+    fn path_goes_out_and_bounces_back() {
+        let points = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(1.0, 1.0),
+        ];
+        assert_near(path_position(&points, 0.0), points[0]);
+        assert_near(path_position(&points, 0.5), Vec2::new(0.5, 0.0));
+        assert_near(path_position(&points, 1.5), Vec2::new(1.0, 0.5));
+        assert_near(path_position(&points, 2.0), points[2]);
+        //On the way back the toy passes the same points in reverse.
+        assert_near(path_position(&points, 2.5), Vec2::new(1.0, 0.5));
+        assert_near(path_position(&points, 3.5), Vec2::new(0.5, 0.0));
+    }
+
+    #[test]
+    fn path_wraps_around() {
+        let points = [Vec2::new(-1.0, 0.0), Vec2::new(1.0, 0.0)];
+        //There and back is 4 long.
+        assert_near(path_position(&points, 4.0), points[0]);
+        assert_near(path_position(&points, 4.5), Vec2::new(-0.5, 0.0));
+        assert_near(path_position(&points, 41.0), Vec2::new(0.0, 0.0));
+        assert_near(path_position(&points, -0.5), Vec2::new(-0.5, 0.0));
+    }
+
+    #[test]
+    fn path_without_length_stays_put() {
+        let point = Vec2::new(0.3, 0.4);
+        assert_near(path_position(&[point], 1.0), point);
+        assert_near(path_position(&[point, point], 1.0), point);
+    }
+
+    #[test]
+    fn table_toys_are_valid() {
+        for toy_spec in table_toys() {
+            assert!(toy_spec.validate().is_ok());
+        }
+    }
+
+    #[test]
+    fn validate_rejects_empty_path() {
+        let toy_spec = ToySpec {
+            position: Vec2::ZERO,
+            shape: ToyShape::MovingTarget,
+            track: ToyTrack::Path {
+                points: Vec::new(),
+                speed: 0.1,
+            },
+        };
+        assert!(toy_spec.validate().is_err());
+    }
+}
@@ -14,13 +14,20 @@ pub struct HalfHeight(pub f32);
 #[derive(Component)]
 pub struct BottomWall;
 
+//Floor x of the left outlane wall. Balls left of it are in the outlane.
+pub const LEFT_OUTLANE_WALL_X: f32 = -0.31;
+
+// Marks the colliders of the fixed walls.
+#[derive(Component)]
+pub struct Wall;
+
 impl Plugin for WallPlugin {
     fn build(&self, app: &mut App) {
         app.add_startup_system(spawn_walls.after(Pinball3DSystems::Main));
     }
 }
 
-fn spawn_walls(
+pub fn spawn_walls(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     asset_server: Res<AssetServer>,
@@ -140,7 +147,8 @@ fn spawn_walls(
                     num_cols,
                     Vec3::new(0.72, 1.0, 0.1),
                 ))
-                .insert(TransformBundle::from(Transform::from_xyz(0.0, -0.01, 0.05)));
+                .insert(TransformBundle::from(Transform::from_xyz(0.0, -0.01, 0.05)))
+                .insert(Wall);
 
             //Collider left wall
             let left_wall_position = Vec3::new(-0.37, -0.51, 0.06);
@@ -150,7 +158,8 @@ fn spawn_walls(
                     left_wall_position.x,
                     left_wall_position.y,
                     left_wall_position.z,
-                )));
+                )))
+                .insert(Wall);
 
             //Collider right wall
             let right_wall_position = Vec3::new(0.37, -0.51, 0.06);
@@ -160,7 +169,8 @@ fn spawn_walls(
                     right_wall_position.x,
                     right_wall_position.y,
                     right_wall_position.z,
-                )));
+                )))
+                .insert(Wall);
 
             //Collider sensor bottom wall
             let bottom_wall_position = Vec3::new(0.0, -1.0, 0.06);
@@ -185,67 +195,34 @@ fn spawn_walls(
         )))
         .id();
 
-    //Left flipper wall
-    let left_flipper_wall_mesh_handle: Handle<Mesh> = meshes.add(Mesh::from(shape::Box::new(
+    //Left outlane wall. Balls between it and the left wall go down the outlane, past the kickback.
+    //It reaches down to the bottom wall, so the kickback can only be reached through the outlane.
+    let left_outlane_wall_mesh_handle: Handle<Mesh> = meshes.add(Mesh::from(shape::Box::new(
         0.01 * 2.0,
-        0.14 * 2.0,
+        0.105 * 2.0,
         0.05 * 2.0,
     )));
-    let left_flipper_wall_position = Vec3::new(-0.24, -0.72, 0.06);
-    let material_flipper_wall = materials.add(Color::CYAN.into());
+    let left_outlane_wall_position = Vec3::new(LEFT_OUTLANE_WALL_X, -0.885, 0.06);
+    let material_outlane_wall = materials.add(Color::CYAN.into());
 
-    let left_flipper_wall = commands
+    let left_outlane_wall = commands
         .spawn(PbrBundle {
-            mesh: left_flipper_wall_mesh_handle.clone(),
-            material: material_flipper_wall.clone(),
+            mesh: left_outlane_wall_mesh_handle.clone(),
+            material: material_outlane_wall.clone(),
             ..default()
         })
         .insert(RigidBody::Fixed)
-        .insert(Collider::cuboid(0.01, 0.14, 0.05))
+        .insert(Collider::cuboid(0.01, 0.105, 0.05))
+        .insert(Wall)
         .insert(CollisionGroups {
             memberships: Group::GROUP_2,
             filters: Group::GROUP_3,
         })
-        .insert(TransformBundle::from(Transform {
-            translation: Vec3::new(
-                left_flipper_wall_position.x,
-                left_flipper_wall_position.y,
-                left_flipper_wall_position.z,
-            ),
-            rotation: Quat::from_rotation_z(1.1),
-            ..default()
-        }))
-        .id();
-
-    //Right flipper wall
-    let right_flipper_wall_mesh_handle: Handle<Mesh> = meshes.add(Mesh::from(shape::Box::new(
-        0.01 * 2.0,
-        0.1 * 2.0,
-        0.05 * 2.0,
-    )));
-    let right_flipper_wall_position = Vec3::new(0.2, -0.74, 0.06);
-
-    let right_flipper_wall = commands
-        .spawn(PbrBundle {
-            mesh: right_flipper_wall_mesh_handle.clone(),
-            material: material_flipper_wall.clone(),
-            ..default()
-        })
-        .insert(RigidBody::Fixed)
-        .insert(Collider::cuboid(0.01, 0.1, 0.05))
-        .insert(CollisionGroups {
-            memberships: Group::GROUP_2,
-            filters: Group::GROUP_3,
-        })
-        .insert(TransformBundle::from(Transform {
-            translation: Vec3::new(
-                right_flipper_wall_position.x,
-                right_flipper_wall_position.y,
-                right_flipper_wall_position.z,
-            ),
-            rotation: Quat::from_rotation_z(-1.1),
-            ..default()
-        }))
+        .insert(TransformBundle::from(Transform::from_xyz(
+            left_outlane_wall_position.x,
+            left_outlane_wall_position.y,
+            left_outlane_wall_position.z,
+        )))
         .id();
 
     //Launcher wall
@@ -265,7 +242,9 @@ fn spawn_walls(
         })
         .insert(RigidBody::Fixed)
         .with_children(|children| {
-            children.spawn(Collider::cuboid(0.01, 0.28, 0.05));
+            children
+                .spawn(Collider::cuboid(0.01, 0.28, 0.05))
+                .insert(Wall);
 
             //small cylinder on top of wall to avoid ball getting stuck.
             children
@@ -274,7 +253,8 @@ fn spawn_walls(
                     translation: Vec3::new(0.0, 0.28, 0.0),
                     rotation: Quat::from_rotation_x(std::f32::consts::PI / 2.0),
                     ..default()
-                }));
+                }))
+                .insert(Wall);
         })
         .insert(CollisionGroups {
             memberships: Group::GROUP_2,
@@ -291,10 +271,7 @@ fn spawn_walls(
         .id();
 
     //Add all walls as children to floor
-    commands.entity(floor).push_children(&[
-        outer_wall,
-        left_flipper_wall,
-        right_flipper_wall,
-        launcher_wall,
-    ]);
+    commands
+        .entity(floor)
+        .push_children(&[outer_wall, left_outlane_wall, launcher_wall]);
 }
@@ -0,0 +1,65 @@
+use pinball3d::environment::{Action, EnvironmentConfig, PinballEnvironment, StepResult};
+
+const SEED: u64 = 7;
+const STEPS: u32 = 200;
+
+//Pulls the plunger, lets go, then flips the flippers in a fixed pattern.
+fn action(step: u32) -> Action {
+    Action {
+        left: step > 60 && step % 7 < 3,
+        right: step > 60 && step % 5 < 2,
+        launch: (20..50).contains(&step),
+        nudge: if step == 100 { Some([0.5, 0.0]) } else { None },
+    }
+}
+
+//Steps and observations as JSON, the same text the environment server sends.
+fn episode(environment: &mut PinballEnvironment, seed: u64) -> Vec<String> {
+    let mut lines = vec![serde_json::to_string(&environment.reset(seed)).unwrap()];
+    for step in 0..STEPS {
+        let result: StepResult = environment.step(&action(step));
+        lines.push(serde_json::to_string(&result).unwrap());
+    }
+    lines
+}
+
+fn assert_reset_step_and_observe(environment: &mut PinballEnvironment) {
+    let observation = environment.reset(SEED);
+    assert_eq!(observation.tick, 1);
+    assert_eq!(observation.score, 0);
+    assert!(!observation.balls.is_empty());
+
+    let result = environment.step(&Action::default());
+    assert_eq!(result.observation.tick, 1 + 4);
+    assert_eq!(result.reward, 0.0);
+    assert!(!result.done);
+    assert_eq!(
+        serde_json::to_string(&environment.observe()).unwrap(),
+        serde_json::to_string(&result.observation).unwrap()
+    );
+}
+
+fn assert_same_seed_gives_same_episode(environment: &mut PinballEnvironment) {
+    let first = episode(environment, SEED);
+    let second = episode(environment, SEED);
+    for (step, (first, second)) in first.iter().zip(second.iter()).enumerate() {
+        assert_eq!(first, second, "Episodes differ at step {}", step);
+    }
+
+    //The balls must not stand still, or there is nothing to compare.
+    let balls = |line: &String| {
+        serde_json::from_str::<serde_json::Value>(line).unwrap()["observation"]["balls"].clone()
+    };
+    assert_ne!(balls(&first[1]), balls(&first[first.len() - 1]));
+}
+
+//One test, headless apps are not meant to be built on several threads at once.
+#[test]
+fn environment_is_deterministic() {
+    let mut environment = PinballEnvironment::new(EnvironmentConfig {
+        ticks_per_step: 4,
+        ..Default::default()
+    });
+    assert_reset_step_and_observe(&mut environment);
+    assert_same_seed_gives_same_episode(&mut environment);
+}
@@ -0,0 +1,150 @@
+use pinball3d::environment::{Action, EnvironmentConfig, PinballEnvironment};
+use pinball3d::MAX_PHYSICS_DT;
+
+//Physics rates the table must play the same at.
+const FRAME_RATES: [f32; 3] = [30.0, 60.0, 144.0];
+
+//A flipper that has not fallen back within this time never will.
+const MAX_FALL_SECONDS: f32 = 1.0;
+
+//Most the fall time and launch speed may differ from the reference at 60 Hz with a fixed timestep.
+const FALL_TIME_TOLERANCE: f32 = 0.01;
+const LAUNCH_SPEED_TOLERANCE: f32 = 0.1;
+
+//Frames of the game that vary this much around the frame time, as they do on a real machine.
+const FRAME_TIME_JITTER: f32 = 0.5;
+
+// How the table is stepped.
+#[derive(Clone, Copy, Debug)]
+enum Timestep {
+    // A fixed physics timestep of one frame.
+    Fixed,
+    // The variable timestep of the shipped game, every frame as long as the others.
+    Game,
+    // The variable timestep of the shipped game, with frames of uneven length.
+    UnevenFrames,
+}
+
+fn environment(frame_rate: f32, timestep: Timestep) -> PinballEnvironment {
+    let mut environment = PinballEnvironment::new(EnvironmentConfig {
+        dt: 1.0 / frame_rate,
+        ticks_per_step: 1,
+        game_timestep: !matches!(timestep, Timestep::Fixed),
+        frame_time_jitter: match timestep {
+            Timestep::UnevenFrames => FRAME_TIME_JITTER,
+            _ => 0.0,
+        },
+        ..Default::default()
+    });
+    environment.reset(0);
+    environment
+}
+
+//Steps with the action held until the given seconds of game time have passed.
+fn hold(environment: &mut PinballEnvironment, action: &Action, seconds: f32) {
+    let end = environment.elapsed_seconds() + seconds;
+    while environment.elapsed_seconds() < end {
+        environment.step(action);
+    }
+}
+
+//Seconds the left flipper takes to fall back halfway after being held up. The crossing is
+//interpolated between ticks, so the result is finer than one frame.
+fn flipper_fall_time(frame_rate: f32, timestep: Timestep) -> f32 {
+    let mut environment = environment(frame_rate, timestep);
+    let up = Action {
+        left: true,
+        ..Default::default()
+    };
+    hold(&mut environment, &up, 0.2);
+    assert!((environment.observe().left_flipper_angle - 0.3).abs() < 1e-4);
+
+    let released = environment.elapsed_seconds();
+    let mut angle = environment.observe().left_flipper_angle;
+    let mut time = released;
+    while time - released < MAX_FALL_SECONDS {
+        let new_angle = environment
+            .step(&Action::default())
+            .observation
+            .left_flipper_angle;
+        let new_time = environment.elapsed_seconds();
+        if new_angle <= 0.0 {
+            let crossing = angle / (angle - new_angle);
+            return time + crossing * (new_time - time) - released;
+        }
+        angle = new_angle;
+        time = new_time;
+    }
+    panic!(
+        "Flipper did not fall back within {} s at {} Hz",
+        MAX_FALL_SECONDS, frame_rate
+    );
+}
+
+//Highest ball speed after a full pull of the plunger.
+fn launch_speed(frame_rate: f32, timestep: Timestep) -> f32 {
+    let mut environment = environment(frame_rate, timestep);
+    hold(&mut environment, &Action::default(), 2.0);
+    let pull = Action {
+        launch: true,
+        ..Default::default()
+    };
+    hold(&mut environment, &pull, 1.2);
+
+    let mut max_speed: f32 = 0.0;
+    let end = environment.elapsed_seconds() + 0.5;
+    while environment.elapsed_seconds() < end {
+        let result = environment.step(&Action::default());
+        for ball in result.observation.balls.iter() {
+            let speed = ball.velocity.iter().map(|v| v * v).sum::<f32>().sqrt();
+            max_speed = max_speed.max(speed);
+        }
+    }
+    max_speed
+}
+
+fn assert_matches_reference(timestep: Timestep) {
+    let reference_fall_time = flipper_fall_time(60.0, Timestep::Fixed);
+    let reference_launch_speed = launch_speed(60.0, Timestep::Fixed);
+    assert!(reference_launch_speed > 0.5);
+
+    for frame_rate in FRAME_RATES {
+        //Frames longer than the physics cap are meant to slow the game down.
+        if let Timestep::UnevenFrames = timestep {
+            if (1.0 + FRAME_TIME_JITTER) / frame_rate > MAX_PHYSICS_DT {
+                continue;
+            }
+        }
+
+        let fall_time = flipper_fall_time(frame_rate, timestep);
+        assert!(
+            (fall_time - reference_fall_time).abs() <= FALL_TIME_TOLERANCE,
+            "Flipper falls in {} s at {} Hz with {:?}, {} s at 60 Hz",
+            fall_time,
+            frame_rate,
+            timestep,
+            reference_fall_time
+        );
+
+        let speed = launch_speed(frame_rate, timestep);
+        assert!(
+            (speed - reference_launch_speed).abs()
+                <= LAUNCH_SPEED_TOLERANCE * reference_launch_speed,
+            "Launch speed {} at {} Hz with {:?}, {} at 60 Hz",
+            speed,
+            frame_rate,
+            timestep,
+            reference_launch_speed
+        );
+    }
+}
+
+//All frame rates are run in one test, headless apps are not meant to be built on several threads at once.
+//The shipped game runs a variable timestep, capped per frame, that must not play in slow motion
+//at the lower frame rates either, nor change with frames of uneven length.
+#[test]
+fn kinematic_motion_is_frame_rate_independent() {
+    assert_matches_reference(Timestep::Fixed);
+    assert_matches_reference(Timestep::Game);
+    assert_matches_reference(Timestep::UnevenFrames);
+}